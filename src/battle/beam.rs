@@ -0,0 +1,231 @@
+use std::time::Duration;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    common::{DamageEvent, DamageKind, DamageSystems},
+    screens::Screen,
+    skill::{
+        FromSkill, Skill, SkillAttributeSet, SkillEffctProcessor, SkillEffectContext,
+        SkillEffectData, SkillResponse, SkillRunContext, SkillRunContextData,
+        SkillRunContextDataBuilder,
+    },
+};
+
+use super::{attack_damage, scale_damage};
+
+///光束的颜色
+const BEAM_COLOR: Color = Color::srgb(1.0, 0.9, 0.4);
+///光束的宽度
+const BEAM_WIDTH: f32 = 4.0;
+
+#[derive(SystemParam)]
+pub struct BeamSystemParam<'w, 's> {
+    pub commands: Commands<'w, 's>,
+    pub caster_q: Query<'w, 's, &'static SkillAttributeSet>,
+}
+
+///光束，在持续时间内周期性地对目标造成伤害
+#[derive(Debug, Component)]
+pub struct BeamSkillEffect {
+    //持续时间，单位为秒
+    pub duration: f32,
+    //造成伤害的间隔，单位为秒
+    pub interval: f32,
+    //每次伤害的倍率
+    pub damage_scale: f32,
+}
+
+impl FromSkill for BeamSkillEffect {
+    fn from_skill(_skill: &Skill, effect: &SkillEffectData) -> Self {
+        BeamSkillEffect {
+            duration: effect.get_value("duration").unwrap_or(2.0),
+            interval: effect.get_value("interval").unwrap_or(0.25),
+            damage_scale: effect.get_value("damage_scale").unwrap_or(0.25),
+        }
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct Beam {
+    pub source: Entity,
+    pub target: Entity,
+    pub damage: i32,
+    interval: Timer,
+    duration: Timer,
+}
+
+impl Beam {
+    pub fn new(source: Entity, target: Entity, damage: i32, interval: f32, duration: f32) -> Self {
+        Beam {
+            source,
+            target,
+            damage,
+            interval: Timer::from_seconds(interval.max(0.01), TimerMode::Repeating),
+            duration: Timer::from_seconds(duration, TimerMode::Once),
+        }
+    }
+
+    ///返回这一帧造成伤害的次数
+    pub fn tick(&mut self, delta: Duration) -> u32 {
+        self.duration.tick(delta);
+        self.interval.tick(delta);
+        self.interval.times_finished_this_tick()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.duration.is_finished()
+    }
+}
+
+pub struct BeamContext {
+    pub caster: Entity,
+    pub target: Entity,
+}
+
+impl SkillRunContextDataBuilder for BeamContext {
+    fn unique_name() -> &'static str {
+        "beam"
+    }
+
+    fn from_skill_run_context_data(data: &SkillRunContextData) -> Option<Self> {
+        let caster = data.get_value::<Entity>(&Self::get_property_name("caster"))?;
+        let target = data.get_value::<Entity>(&Self::get_property_name("target"))?;
+
+        Some(Self {
+            caster: *caster,
+            target: *target,
+        })
+    }
+
+    fn update_skill_run_context_data(&self, data: &mut SkillRunContextData) {
+        data.set_value(&Self::get_property_name("caster"), self.caster);
+        data.set_value(&Self::get_property_name("target"), self.target);
+    }
+}
+
+impl SkillEffectContext for BeamContext {
+    //技能运行数据中没有时使用运行过程的施法者和目标
+    fn from_skill_run_context(_entity: Entity, context: &SkillRunContext) -> Option<Self> {
+        Some(
+            Self::from_skill_run_context_data(&context.data).unwrap_or(Self {
+                caster: context.caster,
+                target: context.target,
+            }),
+        )
+    }
+}
+
+impl<'w, 's> SkillEffctProcessor for BeamSystemParam<'w, 's> {
+    type Effect = BeamSkillEffect;
+    type Context = BeamContext;
+    type Response = ();
+
+    fn process(
+        &mut self,
+        skill_effct: &Self::Effect,
+        context: &BeamContext,
+        _response: &mut SkillResponse,
+    ) {
+        let Ok(attribute_set) = self.caster_q.get(context.caster) else {
+            return;
+        };
+
+        self.commands.spawn((
+            Beam::new(
+                context.caster,
+                context.target,
+                scale_damage(attack_damage(attribute_set), skill_effct.damage_scale),
+                skill_effct.interval,
+                skill_effct.duration,
+            ),
+            Sprite::from_color(BEAM_COLOR, Vec2::ONE),
+            Transform::default(),
+            Name::new("Beam"),
+            DespawnOnExit(Screen::Gameplay),
+        ));
+    }
+}
+
+//光束跟随施法者和目标，周期性地造成伤害
+fn update_beam(
+    mut commands: Commands,
+    mut beam_q: Query<(Entity, &mut Beam, &mut Transform)>,
+    transform_q: Query<&GlobalTransform>,
+    mut writer: MessageWriter<DamageEvent>,
+    time: Res<Time>,
+) {
+    for (entity, mut beam, mut transform) in beam_q.iter_mut() {
+        let (Ok(source), Ok(target)) = (transform_q.get(beam.source), transform_q.get(beam.target))
+        else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        let source = source.translation().truncate();
+        let target = target.translation().truncate();
+        let offset = target - source;
+
+        transform.translation = ((source + target) / 2.0).extend(transform.translation.z);
+        transform.rotation = Quat::from_rotation_z(offset.to_angle());
+        transform.scale = Vec3::new(offset.length(), BEAM_WIDTH, 1.0);
+
+        for _ in 0..beam.tick(time.delta()) {
+            writer.write(DamageEvent {
+                source: beam.source,
+                target: beam.target,
+                amount: beam.damage,
+                kind: DamageKind::Magic,
+            });
+        }
+
+        if beam.is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Update, update_beam.before(DamageSystems::Apply));
+}
+
+#[cfg(test)]
+mod test {
+    use crate::battle::{run_processor, spawn_caster};
+
+    use super::*;
+
+    #[test]
+    fn test_beam_tick() {
+        let mut beam = Beam::new(Entity::PLACEHOLDER, Entity::PLACEHOLDER, 2, 0.25, 1.0);
+
+        assert_eq!(beam.tick(Duration::from_secs_f32(0.1)), 0);
+        assert_eq!(beam.tick(Duration::from_secs_f32(0.5)), 2);
+        assert!(!beam.is_finished());
+
+        beam.tick(Duration::from_secs_f32(0.5));
+        assert!(beam.is_finished());
+    }
+
+    #[test]
+    fn test_beam_process() {
+        let mut world = World::new();
+        let caster = spawn_caster(&mut world, 10.0);
+        let target = Entity::PLACEHOLDER;
+
+        let (response, _) = run_processor::<BeamSystemParam>(
+            &mut world,
+            ("beam", &[("damage_scale", 0.5)]),
+            BeamContext { caster, target },
+        );
+
+        //光束自己造成伤害，不需要执行命令
+        assert!(response.commands().is_empty());
+
+        let mut beam_q = world.query::<&Beam>();
+        let beam = beam_q.single(&world).unwrap();
+        assert_eq!(beam.source, caster);
+        assert_eq!(beam.target, target);
+        assert_eq!(beam.damage, 5);
+    }
+}
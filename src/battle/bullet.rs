@@ -4,8 +4,8 @@ use bevy::{ecs::system::SystemParam, prelude::*};
 use crate::{
     bullet::spawn_bullet,
     skill::{
-        FromSkill, Skill, SkillEffctProcessor, SkillResponse, SkillRunContextData,
-        SkillRunContextDataBuilder,
+        FromSkill, Skill, SkillEffctProcessor, SkillEffectContext, SkillEffectData, SkillResponse,
+        SkillRunContext, SkillRunContextData, SkillRunContextDataBuilder,
     },
 };
 
@@ -15,16 +15,26 @@ pub struct BulletSystemParam<'w, 's> {
     pub asset_server: Res<'w, AssetServer>,
 }
 
+///子弹的默认速度
+const DEFAULT_BULLET_SPEED: f32 = 400.0;
+
 #[derive(Debug, Component)]
-pub struct BulletSkillEffect {}
+pub struct BulletSkillEffect {
+    pub speed: f32,
+}
 
 impl FromSkill for BulletSkillEffect {
-    fn from_skill(_skill: &Skill) -> Self {
-        BulletSkillEffect {}
+    fn from_skill(_skill: &Skill, effect: &SkillEffectData) -> Self {
+        BulletSkillEffect {
+            speed: effect.get_value("speed").unwrap_or(DEFAULT_BULLET_SPEED),
+        }
     }
 }
 
 pub struct BulletContext {
+    //发射子弹的单位
+    pub caster: Entity,
+    pub damage: i32,
     pub direction: Vec2,
     pub layers: CollisionLayers,
     pub bullet_position: Vec2,
@@ -56,7 +66,19 @@ impl SkillRunContextDataBuilder for BulletContext {
             return None;
         };
 
+        let Some(caster) = data.get_value::<Entity>(&Self::get_property_name("caster")) else {
+            tracing::error!("Caster not found.");
+            return None;
+        };
+
+        let Some(damage) = data.get_value::<i32>(&Self::get_property_name("damage")) else {
+            tracing::error!("Damage not found.");
+            return None;
+        };
+
         Some(Self {
+            caster: *caster,
+            damage: *damage,
             direction: *direction,
             layers: *layers,
             bullet_position: *bullet_position,
@@ -64,6 +86,8 @@ impl SkillRunContextDataBuilder for BulletContext {
     }
 
     fn update_skill_run_context_data(&self, data: &mut SkillRunContextData) {
+        data.set_value(&Self::get_property_name("caster"), self.caster);
+        data.set_value(&Self::get_property_name("damage"), self.damage);
         data.set_value(&Self::get_property_name("direction"), self.direction);
         data.set_value(&Self::get_property_name("layers"), self.layers);
         data.set_value(
@@ -73,6 +97,12 @@ impl SkillRunContextDataBuilder for BulletContext {
     }
 }
 
+impl SkillEffectContext for BulletContext {
+    fn from_skill_run_context(_entity: Entity, context: &SkillRunContext) -> Option<Self> {
+        Self::from_skill_run_context_data(&context.data)
+    }
+}
+
 impl<'w, 's> SkillEffctProcessor for BulletSystemParam<'w, 's> {
     type Effect = BulletSkillEffect;
     type Context = BulletContext;
@@ -80,7 +110,7 @@ impl<'w, 's> SkillEffctProcessor for BulletSystemParam<'w, 's> {
 
     fn process(
         &mut self,
-        _skill_effct: &Self::Effect,
+        skill_effct: &Self::Effect,
         context: &BulletContext,
         _response: &mut SkillResponse,
     ) {
@@ -88,8 +118,10 @@ impl<'w, 's> SkillEffctProcessor for BulletSystemParam<'w, 's> {
         spawn_bullet(
             &mut self.commands,
             &self.asset_server,
+            Some(context.caster),
+            context.damage,
             context.layers,
-            context.direction,
+            context.direction * skill_effct.speed,
             context.bullet_position,
         );
     }
@@ -0,0 +1,355 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    common::{DamageKind, EnemyTargets},
+    enemy::Enemy,
+    skill::{
+        FromSkill, Skill, SkillAttributeSet, SkillEffctProcessor, SkillEffectContext,
+        SkillEffectData, SkillResponse, SkillRunContext, SkillRunContextData,
+        SkillRunContextDataBuilder,
+    },
+};
+
+use super::{DamageSkillCommand, SpawnSkillEffectSkillCommand, attack_damage, scale_damage};
+
+#[derive(SystemParam)]
+pub struct ChainSystemParam<'w, 's> {
+    pub enemy_targets_q: Query<'w, 's, &'static EnemyTargets>,
+    pub enemy_q: Query<'w, 's, &'static GlobalTransform, With<Enemy>>,
+    pub caster_q: Query<'w, 's, &'static SkillAttributeSet>,
+}
+
+///闪电链，命中目标后在攻击范围内的敌人之间弹跳，每次弹跳生成新的技能效果
+#[derive(Debug, Component)]
+pub struct ChainSkillEffect {
+    //弹跳的次数，不包括第一个目标
+    pub bounces: usize,
+    //弹跳的最大距离
+    pub range: f32,
+    //每次弹跳后伤害的倍率
+    pub falloff: f32,
+    //弹跳时生成的技能，只包含闪电链
+    pub skill: Skill,
+}
+
+impl FromSkill for ChainSkillEffect {
+    fn from_skill(skill: &Skill, effect: &SkillEffectData) -> Self {
+        ChainSkillEffect {
+            bounces: effect.get_value("bounces").unwrap_or(3.0).max(0.0) as usize,
+            range: effect.get_value("range").unwrap_or(200.0),
+            falloff: effect.get_value("falloff").unwrap_or(0.8),
+            skill: skill.only_effect(effect),
+        }
+    }
+}
+
+///从第一个目标开始，每次弹跳到距离最近且没有被击中的敌人
+pub fn chain_targets(
+    first: (Entity, Vec2),
+    candidates: &[(Entity, Vec2)],
+    bounces: usize,
+    range: f32,
+) -> Vec<Entity> {
+    let mut targets = vec![first.0];
+    let mut current = first.1;
+
+    for _ in 0..bounces {
+        let next = candidates
+            .iter()
+            .filter(|(entity, _)| !targets.contains(entity))
+            .map(|(entity, position)| (*entity, *position, current.distance(*position)))
+            .filter(|(_, _, distance)| *distance <= range)
+            .min_by(|a, b| a.2.total_cmp(&b.2));
+
+        let Some((entity, position, _)) = next else {
+            break;
+        };
+
+        targets.push(entity);
+        current = position;
+    }
+
+    targets
+}
+
+///闪电链的处理结果，弹跳生成的技能效果从中读取伤害和已经击中的敌人
+#[derive(Debug, Clone)]
+pub struct ChainResponse {
+    //下一个目标受到的伤害
+    pub damage: i32,
+    //剩余的弹跳次数
+    pub bounces: usize,
+    pub hit: Vec<Entity>,
+}
+
+impl SkillRunContextDataBuilder for ChainResponse {
+    fn unique_name() -> &'static str {
+        "chain"
+    }
+
+    //第一个目标没有上一次弹跳的结果
+    fn from_skill_run_context_data(data: &SkillRunContextData) -> Option<Self> {
+        let damage = data.get_value::<i32>(&Self::get_property_name("damage"))?;
+        let bounces = data.get_value::<usize>(&Self::get_property_name("bounces"))?;
+        let hit = data.get_value::<Vec<Entity>>(&Self::get_property_name("hit"))?;
+
+        Some(Self {
+            damage: *damage,
+            bounces: *bounces,
+            hit: hit.clone(),
+        })
+    }
+
+    fn update_skill_run_context_data(&self, data: &mut SkillRunContextData) {
+        data.set_value(&Self::get_property_name("damage"), self.damage);
+        data.set_value(&Self::get_property_name("bounces"), self.bounces);
+        data.set_value(&Self::get_property_name("hit"), self.hit.clone());
+    }
+}
+
+pub struct ChainContext {
+    //当前的技能效果实体，弹跳时复制它的运行数据
+    pub effect: Entity,
+    pub caster: Entity,
+    pub target: Entity,
+    //上一次弹跳的结果
+    pub previous: Option<ChainResponse>,
+}
+
+impl SkillEffectContext for ChainContext {
+    fn from_skill_run_context(entity: Entity, context: &SkillRunContext) -> Option<Self> {
+        Some(Self {
+            effect: entity,
+            caster: context.caster,
+            target: context.target,
+            previous: ChainResponse::from_skill_run_context_data(&context.data),
+        })
+    }
+}
+
+impl<'w, 's> SkillEffctProcessor for ChainSystemParam<'w, 's> {
+    type Effect = ChainSkillEffect;
+    type Context = ChainContext;
+    type Response = ChainResponse;
+
+    fn process(
+        &mut self,
+        skill_effct: &Self::Effect,
+        context: &ChainContext,
+        response: &mut SkillResponse,
+    ) -> ChainResponse {
+        //弹跳结束时的结果
+        let finished = ChainResponse {
+            damage: 0,
+            bounces: 0,
+            hit: vec![],
+        };
+
+        let mut chain = match context.previous.clone() {
+            Some(previous) => previous,
+            None => {
+                let Ok(attribute_set) = self.caster_q.get(context.caster) else {
+                    return finished;
+                };
+
+                ChainResponse {
+                    damage: attack_damage(attribute_set),
+                    bounces: skill_effct.bounces,
+                    hit: vec![],
+                }
+            }
+        };
+
+        let Ok(target_position) = self.enemy_q.get(context.target) else {
+            return finished;
+        };
+
+        response.push(DamageSkillCommand {
+            source: context.caster,
+            target: context.target,
+            amount: chain.damage,
+            kind: DamageKind::Magic,
+        });
+
+        chain.hit.push(context.target);
+
+        if chain.bounces > 0 {
+            //只在施法者攻击范围内没有被击中的敌人之间弹跳
+            let candidates = self
+                .enemy_targets_q
+                .get(context.caster)
+                .map(|enemy_targets| {
+                    enemy_targets
+                        .0
+                        .iter()
+                        .filter(|entity| !chain.hit.contains(entity))
+                        .filter_map(|entity| {
+                            self.enemy_q
+                                .get(*entity)
+                                .ok()
+                                .map(|transform| (*entity, transform.translation().truncate()))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            let targets = chain_targets(
+                (context.target, target_position.translation().truncate()),
+                &candidates,
+                1,
+                skill_effct.range,
+            );
+
+            if let Some(next) = targets.get(1) {
+                response.push(SpawnSkillEffectSkillCommand {
+                    skill: skill_effct.skill.clone(),
+                    source: context.effect,
+                    target: *next,
+                });
+            }
+        }
+
+        chain.damage = scale_damage(chain.damage, skill_effct.falloff);
+        chain.bounces = chain.bounces.saturating_sub(1);
+
+        chain
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        battle::{
+            SkillEffectContainer, damage_commands, effect_data, process_skill_effct_system,
+            run_processor, spawn_caster, spawn_skill_effect,
+        },
+        common::DamageEvent,
+        skill::{execute, free, prepare},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_chain_targets() {
+        let [a, b, c, d] = [1, 2, 3, 4].map(|index| Entity::from_raw_u32(index).unwrap());
+
+        let candidates = [
+            (a, Vec2::new(0.0, 0.0)),
+            (b, Vec2::new(150.0, 0.0)),
+            (c, Vec2::new(100.0, 0.0)),
+            (d, Vec2::new(1000.0, 0.0)),
+        ];
+
+        let targets = chain_targets((a, Vec2::ZERO), &candidates, 3, 200.0);
+        assert_eq!(targets, vec![a, c, b]);
+
+        let targets = chain_targets((a, Vec2::ZERO), &candidates, 1, 200.0);
+        assert_eq!(targets, vec![a, c]);
+    }
+
+    fn spawn_enemy(world: &mut World, x: f32) -> Entity {
+        world
+            .spawn((
+                Enemy { reward: 0 },
+                GlobalTransform::from_translation(Vec3::new(x, 0.0, 0.0)),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn test_chain_process() {
+        let mut world = World::new();
+        let target = spawn_enemy(&mut world, 0.0);
+        let near = spawn_enemy(&mut world, 100.0);
+        let far = spawn_enemy(&mut world, 1000.0);
+
+        let caster = spawn_caster(&mut world, 10.0);
+        world
+            .entity_mut(caster)
+            .insert(EnemyTargets(vec![target, near, far]));
+
+        let (response, chain) = run_processor::<ChainSystemParam>(
+            &mut world,
+            ("chain", &[("bounces", 2.0), ("falloff", 0.5)]),
+            ChainContext {
+                effect: Entity::PLACEHOLDER,
+                caster,
+                target,
+                previous: None,
+            },
+        );
+
+        assert_eq!(damage_commands(&response), vec![(target, 10)]);
+
+        //弹跳到最近的敌人
+        let spawn = response.commands()[1]
+            .downcast_ref::<SpawnSkillEffectSkillCommand>()
+            .unwrap();
+        assert_eq!(spawn.source, Entity::PLACEHOLDER);
+        assert_eq!(spawn.target, near);
+
+        assert_eq!(chain.damage, 5);
+        assert_eq!(chain.bounces, 1);
+        assert_eq!(chain.hit, vec![target]);
+    }
+
+    #[test]
+    fn test_chain_bounce() {
+        let mut world = World::new();
+        world.init_resource::<SkillEffectContainer>();
+        world.init_resource::<Messages<DamageEvent>>();
+
+        let a = spawn_enemy(&mut world, 0.0);
+        let b = spawn_enemy(&mut world, 150.0);
+        let c = spawn_enemy(&mut world, 300.0);
+
+        let caster = spawn_caster(&mut world, 10.0);
+        world.entity_mut(caster).insert(EnemyTargets(vec![a, b, c]));
+
+        let skill = Skill::default()
+            .only_effect(&effect_data("chain", &[("bounces", 2.0), ("falloff", 0.5)]));
+
+        world.resource_scope(|world, container: Mut<SkillEffectContainer>| {
+            let mut commands = world.commands();
+
+            spawn_skill_effect(
+                &mut commands,
+                &container,
+                &skill,
+                SkillRunContext {
+                    source: None,
+                    caster,
+                    target: a,
+                    data: Default::default(),
+                },
+            );
+        });
+        world.flush();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(
+            (
+                prepare,
+                process_skill_effct_system::<ChainSystemParam>,
+                execute,
+                free,
+            )
+                .chain(),
+        );
+
+        //每一帧弹跳一次，后续的效果读取上一次的伤害和击中的敌人
+        for _ in 0..4 {
+            schedule.run(&mut world);
+        }
+
+        let damages = world
+            .resource::<Messages<DamageEvent>>()
+            .iter_current_update_messages()
+            .map(|event| (event.target, event.amount))
+            .collect::<Vec<_>>();
+        assert_eq!(damages, vec![(a, 10), (b, 5), (c, 3)]);
+
+        let mut effect_q = world.query::<&SkillRunContext>();
+        assert_eq!(effect_q.iter(&world).count(), 0);
+    }
+}
@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+
+use crate::{
+    common::{DamageEvent, DamageKind, Health, get_max_health},
+    product::ProductMeta,
+    skill::{
+        Skill, SkillAttributeModifier, SkillAttributeSet, SkillCommand, SkillEffectPending,
+        SkillRunContext, StatusEffect, StatusEffects,
+    },
+};
+
+use super::{SkillEffectContainer, spawn_skill_effect};
+
+///造成伤害
+#[derive(Debug)]
+pub struct DamageSkillCommand {
+    pub source: Entity,
+    pub target: Entity,
+    pub amount: i32,
+    pub kind: DamageKind,
+}
+
+impl SkillCommand for DamageSkillCommand {
+    fn execute(&self, world: &mut World) {
+        world.write_message(DamageEvent {
+            source: self.source,
+            target: self.target,
+            amount: self.amount,
+            kind: self.kind,
+        });
+    }
+}
+
+///给目标的属性添加修改器，同一来源的修改器会被替换
+///修改器会一直生效，带有持续时间的修改使用状态
+#[derive(Debug)]
+pub struct AddModifierSkillCommand {
+    pub target: Entity,
+    pub attribute_name: String,
+    pub modifier: SkillAttributeModifier,
+}
+
+impl SkillCommand for AddModifierSkillCommand {
+    fn execute(&self, world: &mut World) {
+        //没有来源的修改器无法被替换和移除
+        if self.modifier.source.is_empty() {
+            tracing::error!("{} modifier source not found.", self.attribute_name);
+            return;
+        }
+
+        if let Some(mut attribute_set) = world.get_mut::<SkillAttributeSet>(self.target) {
+            attribute_set.add_modifier(&self.attribute_name, &self.modifier);
+        }
+    }
+}
+
+///生成后续的技能效果，复制来源效果的运行数据并替换目标
+///后续的技能效果在下一帧处理，可以读取来源效果写入的响应
+#[derive(Debug)]
+pub struct SpawnSkillEffectSkillCommand {
+    pub skill: Skill,
+    //来源的技能效果实体
+    pub source: Entity,
+    pub target: Entity,
+}
+
+impl SkillCommand for SpawnSkillEffectSkillCommand {
+    fn execute(&self, world: &mut World) {
+        let Some(mut context) = world.get::<SkillRunContext>(self.source).cloned() else {
+            return;
+        };
+
+        context.source = Some(self.source);
+        context.target = self.target;
+
+        world.resource_scope(|world, container: Mut<SkillEffectContainer>| {
+            let mut commands = world.commands();
+
+            spawn_skill_effect(&mut commands, &container, &self.skill, context)
+                .insert(SkillEffectPending);
+        });
+
+        world.flush();
+    }
+}
+
+///发放产品
+#[derive(Debug)]
+pub struct ProductSkillCommand {
+    pub product: ProductMeta,
+}
+
+impl SkillCommand for ProductSkillCommand {
+    fn execute(&self, world: &mut World) {
+        world.write_message(self.product.clone());
+    }
+}
+
+///治疗目标
+#[derive(Debug)]
+pub struct HealSkillCommand {
+    pub target: Entity,
+    pub amount: i32,
+}
+
+impl SkillCommand for HealSkillCommand {
+    fn execute(&self, world: &mut World) {
+        let Some(max_health) = world
+            .get::<SkillAttributeSet>(self.target)
+            .map(get_max_health)
+        else {
+            return;
+        };
+
+        if let Some(mut health) = world.get_mut::<Health>(self.target) {
+            health.update(self.amount.max(0), max_health);
+        }
+    }
+}
+
+///给目标添加状态
+#[derive(Debug)]
+pub struct AddStatusEffectSkillCommand {
+    pub target: Entity,
+    pub status_effect: StatusEffect,
+}
+
+impl SkillCommand for AddStatusEffectSkillCommand {
+    fn execute(&self, world: &mut World) {
+        let Ok(mut entity) = world.get_entity_mut(self.target) else {
+            return;
+        };
+
+        if let Some(mut status_effects) = entity.get_mut::<StatusEffects>() {
+            status_effects.apply(self.status_effect.clone());
+        } else {
+            let mut status_effects = StatusEffects::default();
+            status_effects.apply(self.status_effect.clone());
+            entity.insert(status_effects);
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    common::DamageKind,
+    enemy::Enemy,
+    skill::{
+        FromSkill, Skill, SkillAttributeSet, SkillEffctProcessor, SkillEffectContext,
+        SkillEffectData, SkillResponse, SkillRunContext, SkillRunContextData,
+        SkillRunContextDataBuilder,
+    },
+};
+
+use super::{DamageSkillCommand, attack_damage, scale_damage, targets_in_radius};
+
+#[derive(SystemParam)]
+pub struct ExplosionSystemParam<'w, 's> {
+    pub enemy_q: Query<'w, 's, (Entity, &'static GlobalTransform), With<Enemy>>,
+    pub caster_q: Query<'w, 's, &'static SkillAttributeSet>,
+}
+
+///在目标的位置爆炸，对范围内的所有敌人造成伤害
+#[derive(Debug, Component)]
+pub struct ExplosionSkillEffect {
+    pub radius: f32,
+    pub damage_scale: f32,
+}
+
+impl FromSkill for ExplosionSkillEffect {
+    fn from_skill(_skill: &Skill, effect: &SkillEffectData) -> Self {
+        ExplosionSkillEffect {
+            radius: effect.get_value("radius").unwrap_or(150.0),
+            damage_scale: effect.get_value("damage_scale").unwrap_or(1.0),
+        }
+    }
+}
+
+pub struct ExplosionContext {
+    pub caster: Entity,
+    pub target: Entity,
+    //爆炸的中心，没有时在目标的位置爆炸
+    pub center: Option<Vec2>,
+}
+
+impl SkillRunContextDataBuilder for ExplosionContext {
+    fn unique_name() -> &'static str {
+        "explosion"
+    }
+
+    fn from_skill_run_context_data(data: &SkillRunContextData) -> Option<Self> {
+        let caster = data.get_value::<Entity>(&Self::get_property_name("caster"))?;
+        let target = data.get_value::<Entity>(&Self::get_property_name("target"))?;
+        let center = data.get_value::<Vec2>(&Self::get_property_name("center"));
+
+        Some(Self {
+            caster: *caster,
+            target: *target,
+            center: center.copied(),
+        })
+    }
+
+    fn update_skill_run_context_data(&self, data: &mut SkillRunContextData) {
+        data.set_value(&Self::get_property_name("caster"), self.caster);
+        data.set_value(&Self::get_property_name("target"), self.target);
+
+        if let Some(center) = self.center {
+            data.set_value(&Self::get_property_name("center"), center);
+        }
+    }
+}
+
+impl SkillEffectContext for ExplosionContext {
+    //技能运行数据中没有时使用运行过程的施法者和目标
+    fn from_skill_run_context(_entity: Entity, context: &SkillRunContext) -> Option<Self> {
+        Some(
+            Self::from_skill_run_context_data(&context.data).unwrap_or(Self {
+                caster: context.caster,
+                target: context.target,
+                center: None,
+            }),
+        )
+    }
+}
+
+impl<'w, 's> SkillEffctProcessor for ExplosionSystemParam<'w, 's> {
+    type Effect = ExplosionSkillEffect;
+    type Context = ExplosionContext;
+    type Response = ();
+
+    fn process(
+        &mut self,
+        skill_effct: &Self::Effect,
+        context: &ExplosionContext,
+        response: &mut SkillResponse,
+    ) {
+        let Ok(attribute_set) = self.caster_q.get(context.caster) else {
+            return;
+        };
+
+        let Some(center) = context.center.or_else(|| {
+            self.enemy_q
+                .get(context.target)
+                .ok()
+                .map(|(_, transform)| transform.translation().truncate())
+        }) else {
+            return;
+        };
+
+        let targets = targets_in_radius(
+            center,
+            skill_effct.radius,
+            self.enemy_q
+                .iter()
+                .map(|(entity, transform)| (entity, transform.translation().truncate())),
+        );
+
+        let amount = scale_damage(attack_damage(attribute_set), skill_effct.damage_scale);
+
+        for target in targets {
+            response.push(DamageSkillCommand {
+                source: context.caster,
+                target,
+                amount,
+                kind: DamageKind::Physical,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::battle::{damage_commands, run_processor, spawn_caster};
+
+    use super::*;
+
+    #[test]
+    fn test_explosion_process() {
+        let mut world = World::new();
+        let caster = spawn_caster(&mut world, 10.0);
+
+        let mut spawn_enemy = |x: f32| {
+            world
+                .spawn((
+                    Enemy { reward: 0 },
+                    GlobalTransform::from_translation(Vec3::new(x, 0.0, 0.0)),
+                ))
+                .id()
+        };
+
+        let target = spawn_enemy(0.0);
+        let near = spawn_enemy(60.0);
+        let far = spawn_enemy(200.0);
+
+        let (response, _) = run_processor::<ExplosionSystemParam>(
+            &mut world,
+            ("explosion", &[("radius", 80.0), ("damage_scale", 0.5)]),
+            ExplosionContext {
+                caster,
+                target,
+                center: None,
+            },
+        );
+
+        let damages = damage_commands(&response);
+        assert_eq!(damages.len(), 2);
+        assert!(damages.contains(&(target, 5)));
+        assert!(damages.contains(&(near, 5)));
+
+        //施法者可以通过技能运行数据指定爆炸的中心
+        let mut run_context = SkillRunContext {
+            source: None,
+            caster,
+            target: Entity::PLACEHOLDER,
+            data: Default::default(),
+        };
+
+        let context = ExplosionContext::from_skill_run_context(Entity::PLACEHOLDER, &run_context);
+        assert!(context.is_some_and(|context| context.center.is_none()));
+
+        ExplosionContext {
+            caster,
+            target: Entity::PLACEHOLDER,
+            center: Some(Vec2::new(200.0, 0.0)),
+        }
+        .update_skill_run_context_data(&mut run_context.data);
+
+        let (response, _) = run_processor::<ExplosionSystemParam>(
+            &mut world,
+            ("explosion", &[("radius", 80.0), ("damage_scale", 0.5)]),
+            ExplosionContext::from_skill_run_context(Entity::PLACEHOLDER, &run_context).unwrap(),
+        );
+        assert_eq!(damage_commands(&response), vec![(far, 5)]);
+    }
+}
@@ -0,0 +1,172 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    common::{Health, get_max_health},
+    skill::{
+        FromSkill, Skill, SkillAttributeSet, SkillEffctProcessor, SkillEffectContext,
+        SkillEffectData, SkillResponse, SkillRunContext, SkillRunContextData,
+        SkillRunContextDataBuilder,
+    },
+    unit::Unit,
+};
+
+use super::{HealSkillCommand, targets_in_radius};
+
+#[derive(SystemParam)]
+pub struct HealSystemParam<'w, 's> {
+    pub unit_q: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static GlobalTransform,
+            &'static Health,
+            &'static SkillAttributeSet,
+        ),
+        With<Unit>,
+    >,
+    pub caster_q: Query<'w, 's, &'static GlobalTransform>,
+}
+
+///治疗施法者周围受伤的友方单位
+#[derive(Debug, Component)]
+pub struct HealSkillEffect {
+    pub amount: i32,
+    pub radius: f32,
+}
+
+impl FromSkill for HealSkillEffect {
+    fn from_skill(_skill: &Skill, effect: &SkillEffectData) -> Self {
+        HealSkillEffect {
+            amount: effect.get_value("amount").unwrap_or(5.0) as i32,
+            radius: effect.get_value("radius").unwrap_or(200.0),
+        }
+    }
+}
+
+pub struct HealContext {
+    pub caster: Entity,
+    //治疗的中心，没有时以施法者为中心
+    pub center: Option<Vec2>,
+}
+
+impl SkillRunContextDataBuilder for HealContext {
+    fn unique_name() -> &'static str {
+        "heal"
+    }
+
+    fn from_skill_run_context_data(data: &SkillRunContextData) -> Option<Self> {
+        let caster = data.get_value::<Entity>(&Self::get_property_name("caster"))?;
+        let center = data.get_value::<Vec2>(&Self::get_property_name("center"));
+
+        Some(Self {
+            caster: *caster,
+            center: center.copied(),
+        })
+    }
+
+    fn update_skill_run_context_data(&self, data: &mut SkillRunContextData) {
+        data.set_value(&Self::get_property_name("caster"), self.caster);
+
+        if let Some(center) = self.center {
+            data.set_value(&Self::get_property_name("center"), center);
+        }
+    }
+}
+
+impl SkillEffectContext for HealContext {
+    //技能运行数据中没有时使用运行过程的施法者
+    fn from_skill_run_context(_entity: Entity, context: &SkillRunContext) -> Option<Self> {
+        Some(
+            Self::from_skill_run_context_data(&context.data).unwrap_or(Self {
+                caster: context.caster,
+                center: None,
+            }),
+        )
+    }
+}
+
+impl<'w, 's> SkillEffctProcessor for HealSystemParam<'w, 's> {
+    type Effect = HealSkillEffect;
+    type Context = HealContext;
+    type Response = ();
+
+    fn process(
+        &mut self,
+        skill_effct: &Self::Effect,
+        context: &HealContext,
+        response: &mut SkillResponse,
+    ) {
+        let Some(center) = context.center.or_else(|| {
+            self.caster_q
+                .get(context.caster)
+                .ok()
+                .map(|transform| transform.translation().truncate())
+        }) else {
+            return;
+        };
+
+        let targets = targets_in_radius(
+            center,
+            skill_effct.radius,
+            self.unit_q
+                .iter()
+                .filter(|(_, _, health, attribute_set)| {
+                    health.value < get_max_health(attribute_set)
+                })
+                .map(|(entity, transform, ..)| (entity, transform.translation().truncate())),
+        );
+
+        for target in targets {
+            response.push(HealSkillCommand {
+                target,
+                amount: skill_effct.amount,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{battle::run_processor, common::MAX_HEALTH};
+
+    use super::*;
+
+    #[test]
+    fn test_heal_process() {
+        let mut world = World::new();
+
+        let mut spawn_unit = |x: f32, health: i32| {
+            world
+                .spawn((
+                    Unit::default(),
+                    GlobalTransform::from_translation(Vec3::new(x, 0.0, 0.0)),
+                    Health::new(health),
+                    SkillAttributeSet::from_base_values(&[(MAX_HEALTH, 10.0)]),
+                ))
+                .id()
+        };
+
+        let caster = spawn_unit(0.0, 10);
+        let injured = spawn_unit(50.0, 5);
+        spawn_unit(500.0, 5);
+
+        let (response, _) = run_processor::<HealSystemParam>(
+            &mut world,
+            ("heal", &[("amount", 3.0)]),
+            HealContext {
+                caster,
+                center: None,
+            },
+        );
+
+        //满血和范围外的单位不会被治疗
+        let heals = response
+            .commands()
+            .iter()
+            .filter_map(|command| command.downcast_ref::<HealSkillCommand>())
+            .map(|command| (command.target, command.amount))
+            .collect::<Vec<_>>();
+        assert_eq!(heals, vec![(injured, 3)]);
+    }
+}
@@ -0,0 +1,127 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    common::{DamageKind, Health},
+    skill::{
+        FromSkill, Skill, SkillAttributeSet, SkillEffctProcessor, SkillEffectContext,
+        SkillEffectData, SkillResponse, SkillRunContext, SkillRunContextData,
+        SkillRunContextDataBuilder,
+    },
+};
+
+use super::{DamageSkillCommand, attack_damage, scale_damage};
+
+#[derive(SystemParam)]
+pub struct HitScanSystemParam<'w, 's> {
+    pub health_q: Query<'w, 's, (), With<Health>>,
+    pub caster_q: Query<'w, 's, &'static SkillAttributeSet>,
+}
+
+///立即对目标造成伤害
+#[derive(Debug, Component)]
+pub struct HitScanSkillEffect {
+    //伤害的倍率
+    pub damage_scale: f32,
+}
+
+impl FromSkill for HitScanSkillEffect {
+    fn from_skill(_skill: &Skill, effect: &SkillEffectData) -> Self {
+        HitScanSkillEffect {
+            damage_scale: effect.get_value("damage_scale").unwrap_or(1.0),
+        }
+    }
+}
+
+pub struct HitScanContext {
+    pub caster: Entity,
+    pub target: Entity,
+}
+
+impl SkillRunContextDataBuilder for HitScanContext {
+    fn unique_name() -> &'static str {
+        "hit_scan"
+    }
+
+    fn from_skill_run_context_data(data: &SkillRunContextData) -> Option<Self> {
+        let caster = data.get_value::<Entity>(&Self::get_property_name("caster"))?;
+        let target = data.get_value::<Entity>(&Self::get_property_name("target"))?;
+
+        Some(Self {
+            caster: *caster,
+            target: *target,
+        })
+    }
+
+    fn update_skill_run_context_data(&self, data: &mut SkillRunContextData) {
+        data.set_value(&Self::get_property_name("caster"), self.caster);
+        data.set_value(&Self::get_property_name("target"), self.target);
+    }
+}
+
+impl SkillEffectContext for HitScanContext {
+    //技能运行数据中没有时使用运行过程的施法者和目标
+    fn from_skill_run_context(_entity: Entity, context: &SkillRunContext) -> Option<Self> {
+        Some(
+            Self::from_skill_run_context_data(&context.data).unwrap_or(Self {
+                caster: context.caster,
+                target: context.target,
+            }),
+        )
+    }
+}
+
+impl<'w, 's> SkillEffctProcessor for HitScanSystemParam<'w, 's> {
+    type Effect = HitScanSkillEffect;
+    type Context = HitScanContext;
+    type Response = ();
+
+    fn process(
+        &mut self,
+        skill_effct: &Self::Effect,
+        context: &HitScanContext,
+        response: &mut SkillResponse,
+    ) {
+        if !self.health_q.contains(context.target) {
+            return;
+        }
+
+        let Ok(attribute_set) = self.caster_q.get(context.caster) else {
+            return;
+        };
+
+        response.push(DamageSkillCommand {
+            source: context.caster,
+            target: context.target,
+            amount: scale_damage(attack_damage(attribute_set), skill_effct.damage_scale),
+            kind: DamageKind::Physical,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::battle::{damage_commands, run_processor, spawn_caster};
+
+    use super::*;
+
+    #[test]
+    fn test_hit_scan_process() {
+        let mut world = World::new();
+        let caster = spawn_caster(&mut world, 10.0);
+        let target = world.spawn(Health::new(10)).id();
+
+        let mut process = |target| {
+            run_processor::<HitScanSystemParam>(
+                &mut world,
+                ("hit_scan", &[("damage_scale", 1.5)]),
+                HitScanContext { caster, target },
+            )
+            .0
+        };
+
+        assert_eq!(damage_commands(&process(target)), vec![(target, 15)]);
+
+        //没有生命值的目标不会受到伤害
+        assert!(process(Entity::PLACEHOLDER).commands().is_empty());
+    }
+}
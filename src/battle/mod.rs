@@ -1,30 +1,166 @@
+mod beam;
 mod bullet;
+mod chain;
+mod command;
+mod explosion;
+mod heal;
+mod hit_scan;
+mod modifier;
+mod product;
+mod status;
 
+pub use beam::*;
 pub use bullet::*;
+pub use chain::*;
+pub use command::*;
+pub use explosion::*;
+pub use heal::*;
+pub use hit_scan::*;
+pub use modifier::*;
+pub use product::*;
+pub use status::*;
 
-use bevy::prelude::*;
+use bevy::{
+    ecs::system::{StaticSystemParam, SystemParam},
+    platform::collections::HashMap,
+    prelude::*,
+};
 
-use crate::skill::{
-    FromSkill, Skill, SkillResponse, SkillRunContext, SkillRunContextData, SkillSystems,
-    process_skill_effct,
+use crate::{
+    common::ATTACK_DAMAGE,
+    skill::{
+        FromSkill, Skill, SkillAttributeSet, SkillEffctProcessor, SkillEffectData, SkillResponse,
+        SkillRunContext, SkillRunContextData, SkillSystems, process_skill_effct,
+    },
 };
 
-fn process_bullet_skill_effct_system(
-    mut processor: BulletSystemParam,
-    skill_effct_q: Query<(&BulletSkillEffect, &mut SkillRunContext, &mut SkillResponse)>,
+type SkillEffectBuilder = fn(&Skill, &SkillEffectData, &mut EntityCommands);
+
+fn insert_skill_effect<T: FromSkill + Component>(
+    skill: &Skill,
+    effect: &SkillEffectData,
+    entity_commands: &mut EntityCommands,
 ) {
-    process_skill_effct::<BulletSystemParam>(&mut processor, skill_effct_q);
+    entity_commands.insert(T::from_skill(skill, effect));
+}
+
+///技能效果的名称和对应的组件
+#[derive(Debug, Resource)]
+pub struct SkillEffectContainer(HashMap<String, SkillEffectBuilder>);
+
+impl Default for SkillEffectContainer {
+    fn default() -> Self {
+        let mut container = SkillEffectContainer::empty();
+        container.register::<BulletSkillEffect>("bullet");
+        container.register::<HitScanSkillEffect>("hit_scan");
+        container.register::<ExplosionSkillEffect>("explosion");
+        container.register::<ChainSkillEffect>("chain");
+        container.register::<BeamSkillEffect>("beam");
+        container.register::<HealSkillEffect>("heal");
+        container.register::<ProductSkillEffect>("product");
+        container.register::<ModifierSkillEffect>("modifier");
+
+        for name in ["slow", "stun", "haste", "damage_amp", "burn", "poison"] {
+            container.register_builder(name, status::insert_status_skill_effect);
+        }
+
+        container
+    }
+}
+
+impl SkillEffectContainer {
+    pub fn register<T: FromSkill + Component>(&mut self, name: &str) {
+        self.0.insert(
+            name.to_string(),
+            insert_skill_effect::<T> as SkillEffectBuilder,
+        );
+    }
+
+    ///注册自定义的构建函数，例如多个效果合并到同一个组件
+    pub fn register_builder(&mut self, name: &str, builder: SkillEffectBuilder) {
+        self.0.insert(name.to_string(), builder);
+    }
+
+    pub fn empty() -> Self {
+        SkillEffectContainer(Default::default())
+    }
+
+    //根据技能的定义添加效果组件
+    pub fn insert_skill_effects(&self, skill: &Skill, entity_commands: &mut EntityCommands) {
+        for effect in skill.effects().iter() {
+            if let Some(builder) = self.0.get(&effect.name) {
+                builder(skill, effect, entity_commands);
+            } else {
+                tracing::error!(
+                    "{} skill effect not match in {}.",
+                    effect.name,
+                    skill.item_name()
+                );
+            }
+        }
+    }
+}
+
+///施法者当前的攻击力
+pub fn attack_damage(attribute_set: &SkillAttributeSet) -> i32 {
+    attribute_set
+        .get_current_value(ATTACK_DAMAGE)
+        .unwrap_or_default()
+}
+
+///按照倍率计算伤害
+pub fn scale_damage(damage: i32, scale: f32) -> i32 {
+    (damage as f32 * scale).round() as i32
+}
+
+///范围内的实体
+pub fn targets_in_radius(
+    center: Vec2,
+    radius: f32,
+    candidates: impl Iterator<Item = (Entity, Vec2)>,
+) -> Vec<Entity> {
+    candidates
+        .filter(|(_, position)| center.distance(*position) <= radius)
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+///处理一种技能效果的系统，P为效果的处理器
+pub fn process_skill_effct_system<P>(
+    mut processor: StaticSystemParam<P>,
+    skill_effct_q: Query<(Entity, &P::Effect, &mut SkillRunContext, &mut SkillResponse)>,
+) where
+    P: SkillEffctProcessor + SystemParam + 'static,
+    for<'w, 's> P::Item<'w, 's>: SkillEffctProcessor<Effect = P::Effect>,
+{
+    process_skill_effct(&mut *processor, skill_effct_q);
 }
 
 pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SkillEffectContainer>();
+
     app.add_systems(
         Last,
-        process_bullet_skill_effct_system.in_set(SkillSystems::Update),
+        (
+            process_skill_effct_system::<BulletSystemParam>,
+            process_skill_effct_system::<HitScanSystemParam>,
+            process_skill_effct_system::<ExplosionSystemParam>,
+            process_skill_effct_system::<ChainSystemParam>,
+            process_skill_effct_system::<BeamSystemParam>,
+            process_skill_effct_system::<StatusSystemParam>,
+            process_skill_effct_system::<HealSystemParam>,
+            process_skill_effct_system::<ProductSystemParam>,
+            process_skill_effct_system::<ModifierSystemParam>,
+        )
+            .in_set(SkillSystems::Update),
     );
+
+    beam::plugin(app);
 }
 
 pub fn execute_skill(
     commands: &mut Commands,
+    container: &SkillEffectContainer,
     skill: &Skill,
     caster: Entity,
     targets: Vec<Entity>,
@@ -32,17 +168,150 @@ pub fn execute_skill(
     data: SkillRunContextData,
 ) {
     for target in targets.iter() {
-        let bullet_skill_effect = BulletSkillEffect::from_skill(skill);
-
-        commands.spawn((
-            bullet_skill_effect,
-            SkillRunContext {
-                source: source.clone(),
-                data: data.clone(),
-                caster,
-                target: *target,
-            },
-            SkillResponse::empty(),
-        ));
+        let context = SkillRunContext {
+            source,
+            data: data.clone(),
+            caster,
+            target: *target,
+        };
+
+        spawn_skill_effect(commands, container, skill, context);
+    }
+}
+
+///生成技能效果实体
+pub fn spawn_skill_effect<'a>(
+    commands: &'a mut Commands,
+    container: &SkillEffectContainer,
+    skill: &Skill,
+    context: SkillRunContext,
+) -> EntityCommands<'a> {
+    let mut entity_commands = commands.spawn((context, SkillResponse::empty()));
+
+    container.insert_skill_effects(skill, &mut entity_commands);
+
+    entity_commands
+}
+
+#[cfg(test)]
+pub(crate) fn effect_data(name: &str, values: &[(&str, f32)]) -> SkillEffectData {
+    use crate::skill::SkillEffectValueData;
+
+    SkillEffectData {
+        name: name.to_string(),
+        values: values
+            .iter()
+            .map(|(name, value)| SkillEffectValueData {
+                name: name.to_string(),
+                value: *value,
+            })
+            .collect(),
+    }
+}
+
+//测试时处理一次技能效果，返回处理的响应和结果
+#[cfg(test)]
+fn process_once<P>(
+    In((effect, context)): In<(P::Effect, P::Context)>,
+    mut processor: StaticSystemParam<P>,
+) -> (SkillResponse, P::Response)
+where
+    P: SkillEffctProcessor + SystemParam + 'static,
+    P::Context: Send + 'static,
+    P::Response: Send + 'static,
+    for<'w, 's> P::Item<'w, 's>:
+        SkillEffctProcessor<Effect = P::Effect, Context = P::Context, Response = P::Response>,
+{
+    let mut response = SkillResponse::empty();
+    let result = processor.process(&effect, &context, &mut response);
+    (response, result)
+}
+
+///按照效果的名称和参数创建效果，用处理器处理一次
+#[cfg(test)]
+pub(crate) fn run_processor<P>(
+    world: &mut World,
+    effect: (&str, &[(&str, f32)]),
+    context: P::Context,
+) -> (SkillResponse, P::Response)
+where
+    P: SkillEffctProcessor + SystemParam + 'static,
+    P::Context: Send + 'static,
+    P::Response: Send + 'static,
+    for<'w, 's> P::Item<'w, 's>:
+        SkillEffctProcessor<Effect = P::Effect, Context = P::Context, Response = P::Response>,
+{
+    use bevy::ecs::system::RunSystemOnce;
+
+    let (name, values) = effect;
+    let effect = P::Effect::from_skill(&Skill::default(), &effect_data(name, values));
+
+    world
+        .run_system_once_with(process_once::<P>, (effect, context))
+        .unwrap()
+}
+
+#[cfg(test)]
+pub(crate) fn spawn_caster(world: &mut World, damage: f32) -> Entity {
+    world
+        .spawn(SkillAttributeSet::from_base_values(&[(
+            ATTACK_DAMAGE,
+            damage,
+        )]))
+        .id()
+}
+
+//响应中的伤害命令，按照目标和伤害返回
+#[cfg(test)]
+pub(crate) fn damage_commands(response: &SkillResponse) -> Vec<(Entity, i32)> {
+    response
+        .commands()
+        .iter()
+        .filter_map(|command| command.downcast_ref::<DamageSkillCommand>())
+        .map(|command| (command.target, command.amount))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::skill::SkillData;
+
+    use super::*;
+
+    #[test]
+    fn test_scale_damage() {
+        assert_eq!(scale_damage(10, 1.0), 10);
+        assert_eq!(scale_damage(10, 0.25), 3);
+        assert_eq!(scale_damage(10, 0.0), 0);
+    }
+
+    #[test]
+    fn test_targets_in_radius() {
+        let a = Entity::from_raw_u32(1).unwrap();
+        let b = Entity::from_raw_u32(2).unwrap();
+
+        let targets = targets_in_radius(
+            Vec2::ZERO,
+            100.0,
+            [(a, Vec2::new(50.0, 0.0)), (b, Vec2::new(150.0, 0.0))].into_iter(),
+        );
+
+        assert_eq!(targets, vec![a]);
+    }
+
+    #[test]
+    fn test_skill_data_effects() {
+        let container = SkillEffectContainer::default();
+
+        for source in [
+            include_str!("../../assets/skill/arrow.skill_data.yaml"),
+            include_str!("../../assets/skill/lightning.skill_data.yaml"),
+        ] {
+            let data: SkillData = yaml_serde::from_str(source).unwrap();
+
+            for effect in data.effects.iter() {
+                assert!(container.0.contains_key(&effect.name), "{}", effect.name);
+            }
+        }
     }
 }
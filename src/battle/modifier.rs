@@ -0,0 +1,208 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::skill::{
+    FromSkill, Skill, SkillAttributeModifier, SkillAttributeModifierOperation, SkillAttributeSet,
+    SkillEffctProcessor, SkillEffectContext, SkillEffectData, SkillResponse, SkillRunContext,
+    SkillRunContextData, SkillRunContextDataBuilder, StatusEffect,
+};
+
+use super::{AddModifierSkillCommand, AddStatusEffectSkillCommand};
+
+#[derive(SystemParam)]
+pub struct ModifierSystemParam<'w, 's> {
+    pub attribute_set_q: Query<'w, 's, &'static SkillAttributeSet>,
+}
+
+///修改目标的属性，每个参数对应一个属性，数值为增加的值
+///同一个技能重复修改时替换之前的修改器，设置duration时修改在持续时间后移除
+#[derive(Debug, Component)]
+pub struct ModifierSkillEffect {
+    //修改器的来源
+    pub source: String,
+    pub modifiers: Vec<(String, f32)>,
+    //持续时间，没有时一直生效
+    pub duration: Option<f32>,
+}
+
+impl ModifierSkillEffect {
+    pub fn get_source(item_name: &str) -> String {
+        format!("__skill_{}__", item_name)
+    }
+
+    //有持续时间的修改作为状态添加，到时后由状态移除
+    fn create_status_effect(&self, duration: f32) -> StatusEffect {
+        self.modifiers.iter().fold(
+            StatusEffect::new(&self.source, duration),
+            |status_effect, (attribute_name, value)| {
+                status_effect.with_modifier(
+                    attribute_name,
+                    SkillAttributeModifierOperation::Absolute,
+                    *value,
+                )
+            },
+        )
+    }
+}
+
+impl FromSkill for ModifierSkillEffect {
+    fn from_skill(skill: &Skill, effect: &SkillEffectData) -> Self {
+        ModifierSkillEffect {
+            source: ModifierSkillEffect::get_source(skill.item_name()),
+            modifiers: effect
+                .values
+                .iter()
+                .filter(|value| value.name != "duration")
+                .map(|value| (value.name.clone(), value.value))
+                .collect(),
+            duration: effect.get_value("duration"),
+        }
+    }
+}
+
+pub struct ModifierContext {
+    pub target: Entity,
+}
+
+impl SkillRunContextDataBuilder for ModifierContext {
+    fn unique_name() -> &'static str {
+        "modifier"
+    }
+
+    fn from_skill_run_context_data(data: &SkillRunContextData) -> Option<Self> {
+        let target = data.get_value::<Entity>(&Self::get_property_name("target"))?;
+
+        Some(Self { target: *target })
+    }
+
+    fn update_skill_run_context_data(&self, data: &mut SkillRunContextData) {
+        data.set_value(&Self::get_property_name("target"), self.target);
+    }
+}
+
+impl SkillEffectContext for ModifierContext {
+    //技能运行数据中没有时使用运行过程的目标
+    fn from_skill_run_context(_entity: Entity, context: &SkillRunContext) -> Option<Self> {
+        Some(
+            Self::from_skill_run_context_data(&context.data).unwrap_or(Self {
+                target: context.target,
+            }),
+        )
+    }
+}
+
+impl<'w, 's> SkillEffctProcessor for ModifierSystemParam<'w, 's> {
+    type Effect = ModifierSkillEffect;
+    type Context = ModifierContext;
+    type Response = ();
+
+    fn process(
+        &mut self,
+        skill_effct: &Self::Effect,
+        context: &ModifierContext,
+        response: &mut SkillResponse,
+    ) {
+        let Ok(attribute_set) = self.attribute_set_q.get(context.target) else {
+            return;
+        };
+
+        if let Some(duration) = skill_effct.duration {
+            response.push(AddStatusEffectSkillCommand {
+                target: context.target,
+                status_effect: skill_effct.create_status_effect(duration),
+            });
+            return;
+        }
+
+        for (attribute_name, value) in skill_effct.modifiers.iter() {
+            //目标没有的属性不修改
+            if attribute_set.skill_attribute(attribute_name).is_none() {
+                continue;
+            }
+
+            response.push(AddModifierSkillCommand {
+                target: context.target,
+                attribute_name: attribute_name.clone(),
+                modifier: SkillAttributeModifier {
+                    value: *value,
+                    source: skill_effct.source.clone(),
+                    ..Default::default()
+                },
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{
+        battle::run_processor,
+        common::{ARMOR, MAGIC_RESISTANCE},
+        skill::{SkillCommand, StatusEffects},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_modifier_process() {
+        let mut world = World::new();
+        let target = world
+            .spawn(SkillAttributeSet::from_base_values(&[(ARMOR, 10.0)]))
+            .id();
+
+        let (mut response, _) = run_processor::<ModifierSystemParam>(
+            &mut world,
+            ("modifier", &[(ARMOR, -3.0), (MAGIC_RESISTANCE, -3.0)]),
+            ModifierContext { target },
+        );
+
+        //目标没有魔法抗性
+        let commands = response.take_commands();
+        assert_eq!(commands.len(), 1);
+
+        let command = commands[0]
+            .downcast_ref::<AddModifierSkillCommand>()
+            .unwrap();
+        assert_eq!(command.attribute_name, ARMOR);
+        assert_eq!(command.modifier.source, ModifierSkillEffect::get_source(""));
+
+        //重复执行时替换之前的修改器
+        command.execute(&mut world);
+        command.execute(&mut world);
+
+        let attribute_set = world.get::<SkillAttributeSet>(target).unwrap();
+        assert_eq!(attribute_set.get_current_value(ARMOR), Some(7));
+    }
+
+    #[test]
+    fn test_modifier_duration() {
+        let mut world = World::new();
+        let target = world
+            .spawn(SkillAttributeSet::from_base_values(&[(ARMOR, 10.0)]))
+            .id();
+
+        let (mut response, _) = run_processor::<ModifierSystemParam>(
+            &mut world,
+            ("modifier", &[(ARMOR, -3.0), ("duration", 1.0)]),
+            ModifierContext { target },
+        );
+
+        //有持续时间时作为状态添加
+        let commands = response.take_commands();
+        assert_eq!(commands.len(), 1);
+        commands[0].execute(&mut world);
+
+        let mut entity = world.entity_mut(target);
+        let mut status_effects = entity.take::<StatusEffects>().unwrap();
+        let mut attribute_set = entity.take::<SkillAttributeSet>().unwrap();
+
+        status_effects.update_attribute_set(&mut attribute_set);
+        assert_eq!(attribute_set.get_current_value(ARMOR), Some(7));
+
+        //到时后移除修改
+        status_effects.tick(target, Duration::from_secs_f32(1.0));
+        status_effects.update_attribute_set(&mut attribute_set);
+        assert_eq!(attribute_set.get_current_value(ARMOR), Some(10));
+    }
+}
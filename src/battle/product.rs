@@ -0,0 +1,126 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    product::ProductMeta,
+    skill::{
+        FromSkill, Skill, SkillEffctProcessor, SkillEffectContext, SkillEffectData, SkillResponse,
+        SkillRunContext, SkillRunContextData, SkillRunContextDataBuilder,
+    },
+};
+
+use super::ProductSkillCommand;
+
+#[derive(SystemParam)]
+pub struct ProductSystemParam<'w, 's> {
+    pub entity_q: Query<'w, 's, ()>,
+}
+
+///发放产品，每个参数对应一种产品
+#[derive(Debug, Component)]
+pub struct ProductSkillEffect {
+    pub products: Vec<ProductMeta>,
+}
+
+impl FromSkill for ProductSkillEffect {
+    fn from_skill(_skill: &Skill, effect: &SkillEffectData) -> Self {
+        ProductSkillEffect {
+            products: effect
+                .values
+                .iter()
+                .map(|value| ProductMeta {
+                    name: value.name.clone(),
+                    value: value.value,
+                })
+                .collect(),
+        }
+    }
+}
+
+pub struct ProductContext {
+    pub caster: Entity,
+}
+
+impl SkillRunContextDataBuilder for ProductContext {
+    fn unique_name() -> &'static str {
+        "product"
+    }
+
+    fn from_skill_run_context_data(data: &SkillRunContextData) -> Option<Self> {
+        let caster = data.get_value::<Entity>(&Self::get_property_name("caster"))?;
+
+        Some(Self { caster: *caster })
+    }
+
+    fn update_skill_run_context_data(&self, data: &mut SkillRunContextData) {
+        data.set_value(&Self::get_property_name("caster"), self.caster);
+    }
+}
+
+impl SkillEffectContext for ProductContext {
+    //技能运行数据中没有时使用运行过程的施法者
+    fn from_skill_run_context(_entity: Entity, context: &SkillRunContext) -> Option<Self> {
+        Some(
+            Self::from_skill_run_context_data(&context.data).unwrap_or(Self {
+                caster: context.caster,
+            }),
+        )
+    }
+}
+
+impl<'w, 's> SkillEffctProcessor for ProductSystemParam<'w, 's> {
+    type Effect = ProductSkillEffect;
+    type Context = ProductContext;
+    type Response = ();
+
+    fn process(
+        &mut self,
+        skill_effct: &Self::Effect,
+        context: &ProductContext,
+        response: &mut SkillResponse,
+    ) {
+        //施法者已经被销毁时不发放
+        if !self.entity_q.contains(context.caster) {
+            return;
+        }
+
+        for product in skill_effct.products.iter() {
+            response.push(ProductSkillCommand {
+                product: product.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::battle::run_processor;
+
+    use super::*;
+
+    #[test]
+    fn test_product_process() {
+        let mut world = World::new();
+        let caster = world.spawn_empty().id();
+
+        let mut process = |caster| {
+            run_processor::<ProductSystemParam>(
+                &mut world,
+                ("product", &[("sunlight", 5.0)]),
+                ProductContext { caster },
+            )
+            .0
+        };
+
+        let response = process(caster);
+        let products = response
+            .commands()
+            .iter()
+            .filter_map(|command| command.downcast_ref::<ProductSkillCommand>())
+            .map(|command| (command.product.name.as_str(), command.product.value))
+            .collect::<Vec<_>>();
+        assert_eq!(products, vec![("sunlight", 5.0)]);
+
+        //施法者已经被销毁时不发放
+        assert!(process(Entity::PLACEHOLDER).commands().is_empty());
+    }
+}
@@ -0,0 +1,266 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    common::{DAMAGE_TAKEN, DamageEvent, DamageKind, MOVE_SPEED},
+    skill::{
+        FromSkill, Skill, SkillAttributeModifierOperation, SkillEffctProcessor, SkillEffectContext,
+        SkillEffectData, SkillResponse, SkillRunContext, SkillRunContextData,
+        SkillRunContextDataBuilder, StatusEffect, StatusEffectTick, StatusStack,
+    },
+};
+
+use super::AddStatusEffectSkillCommand;
+
+///根据技能效果的名称创建状态
+pub fn create_status_effect(effect: &SkillEffectData) -> Option<StatusEffect> {
+    let duration = effect.get_value("duration");
+
+    let status_effect = match effect.name.as_str() {
+        //按照比例降低移动速度
+        "slow" => StatusEffect::new("slow", duration.unwrap_or(2.0)).with_modifier(
+            MOVE_SPEED,
+            SkillAttributeModifierOperation::Percentage,
+            -effect
+                .get_value("percentage")
+                .unwrap_or(0.5)
+                .clamp(0.0, 1.0),
+        ),
+        //无法移动
+        "stun" => StatusEffect::new("stun", duration.unwrap_or(1.0)).with_modifier(
+            MOVE_SPEED,
+            SkillAttributeModifierOperation::Overload,
+            0.0,
+        ),
+        //按照比例提高移动速度
+        "haste" => StatusEffect::new("haste", duration.unwrap_or(3.0)).with_modifier(
+            MOVE_SPEED,
+            SkillAttributeModifierOperation::Percentage,
+            effect.get_value("percentage").unwrap_or(0.3).max(0.0),
+        ),
+        //受到的伤害按照百分比增加，可以叠加
+        "damage_amp" => StatusEffect::new("damage_amp", duration.unwrap_or(3.0))
+            .with_stack(StatusStack::Stack {
+                max_stacks: effect.get_value("max_stacks").unwrap_or(3.0).max(1.0) as u32,
+            })
+            .with_modifier(
+                DAMAGE_TAKEN,
+                SkillAttributeModifierOperation::Absolute,
+                effect.get_value("percentage").unwrap_or(0.1) * 100.0,
+            ),
+        //周期造成魔法伤害
+        "burn" => StatusEffect::new("burn", duration.unwrap_or(3.0)).with_tick(
+            effect.get_value("interval").unwrap_or(0.5),
+            effect.get_value("damage").unwrap_or(2.0),
+            burn_tick,
+        ),
+        //周期造成真实伤害，可以叠加
+        "poison" => StatusEffect::new("poison", duration.unwrap_or(4.0))
+            .with_stack(StatusStack::Stack {
+                max_stacks: effect.get_value("max_stacks").unwrap_or(5.0).max(1.0) as u32,
+            })
+            .with_tick(
+                effect.get_value("interval").unwrap_or(1.0),
+                effect.get_value("damage").unwrap_or(1.0),
+                poison_tick,
+            ),
+        _ => return None,
+    };
+
+    Some(status_effect)
+}
+
+//每层造成的伤害
+fn tick_damage(tick: &StatusEffectTick, kind: DamageKind) -> DamageEvent {
+    DamageEvent {
+        source: tick.caster.unwrap_or(tick.owner),
+        target: tick.owner,
+        amount: (tick.value * tick.stacks as f32).round() as i32,
+        kind,
+    }
+}
+
+fn burn_tick(tick: &StatusEffectTick, commands: &mut Commands) {
+    commands.write_message(tick_damage(tick, DamageKind::Magic));
+}
+
+fn poison_tick(tick: &StatusEffectTick, commands: &mut Commands) {
+    commands.write_message(tick_damage(tick, DamageKind::True));
+}
+
+//每个状态效果对应技能中的一个效果，同一个技能可以添加多个状态
+pub(super) fn insert_status_skill_effect(
+    skill: &Skill,
+    effect: &SkillEffectData,
+    entity_commands: &mut EntityCommands,
+) {
+    let skill_effct = StatusSkillEffect::from_skill(skill, effect);
+
+    if skill_effct.status_effects.is_empty() {
+        tracing::error!(
+            "{} status effect not match in {}.",
+            effect.name,
+            skill.item_name()
+        );
+        return;
+    }
+
+    entity_commands
+        .entry::<StatusSkillEffect>()
+        .or_default()
+        .and_modify(move |mut current| current.status_effects.extend(skill_effct.status_effects));
+}
+
+#[derive(SystemParam)]
+pub struct StatusSystemParam<'w, 's> {
+    pub entity_q: Query<'w, 's, ()>,
+}
+
+///给目标添加状态
+#[derive(Debug, Component, Default)]
+pub struct StatusSkillEffect {
+    pub status_effects: Vec<StatusEffect>,
+}
+
+impl FromSkill for StatusSkillEffect {
+    fn from_skill(_skill: &Skill, effect: &SkillEffectData) -> Self {
+        StatusSkillEffect {
+            status_effects: create_status_effect(effect).into_iter().collect(),
+        }
+    }
+}
+
+pub struct StatusContext {
+    pub caster: Entity,
+    pub target: Entity,
+}
+
+impl SkillRunContextDataBuilder for StatusContext {
+    fn unique_name() -> &'static str {
+        "status"
+    }
+
+    fn from_skill_run_context_data(data: &SkillRunContextData) -> Option<Self> {
+        let caster = data.get_value::<Entity>(&Self::get_property_name("caster"))?;
+        let target = data.get_value::<Entity>(&Self::get_property_name("target"))?;
+
+        Some(Self {
+            caster: *caster,
+            target: *target,
+        })
+    }
+
+    fn update_skill_run_context_data(&self, data: &mut SkillRunContextData) {
+        data.set_value(&Self::get_property_name("caster"), self.caster);
+        data.set_value(&Self::get_property_name("target"), self.target);
+    }
+}
+
+impl SkillEffectContext for StatusContext {
+    //技能运行数据中没有时使用运行过程的施法者和目标
+    fn from_skill_run_context(_entity: Entity, context: &SkillRunContext) -> Option<Self> {
+        Some(
+            Self::from_skill_run_context_data(&context.data).unwrap_or(Self {
+                caster: context.caster,
+                target: context.target,
+            }),
+        )
+    }
+}
+
+impl<'w, 's> SkillEffctProcessor for StatusSystemParam<'w, 's> {
+    type Effect = StatusSkillEffect;
+    type Context = StatusContext;
+    type Response = ();
+
+    fn process(
+        &mut self,
+        skill_effct: &Self::Effect,
+        context: &StatusContext,
+        response: &mut SkillResponse,
+    ) {
+        if !self.entity_q.contains(context.target) {
+            return;
+        }
+
+        for status_effect in skill_effct.status_effects.iter() {
+            response.push(AddStatusEffectSkillCommand {
+                target: context.target,
+                status_effect: status_effect.clone().with_caster(context.caster),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::battle::{effect_data, run_processor};
+
+    use super::*;
+
+    #[test]
+    fn test_status_process() {
+        let mut world = World::new();
+        let caster = world.spawn_empty().id();
+        let target = world.spawn_empty().id();
+
+        let mut process = |target| {
+            run_processor::<StatusSystemParam>(
+                &mut world,
+                ("slow", &[]),
+                StatusContext { caster, target },
+            )
+            .0
+        };
+
+        let response = process(target);
+        let status_effects = response
+            .commands()
+            .iter()
+            .filter_map(|command| command.downcast_ref::<AddStatusEffectSkillCommand>())
+            .map(|command| {
+                (
+                    command.target,
+                    command.status_effect.name.as_str(),
+                    command.status_effect.caster,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(status_effects, vec![(target, "slow", Some(caster))]);
+
+        //目标已经被销毁时不添加
+        assert!(process(Entity::PLACEHOLDER).commands().is_empty());
+    }
+
+    #[test]
+    fn test_create_status_effect() {
+        let slow = create_status_effect(&effect_data("slow", &[("percentage", 0.4)])).unwrap();
+        assert_eq!(slow.name, "slow");
+        assert_eq!(slow.stack, StatusStack::Refresh);
+
+        let poison = create_status_effect(&effect_data("poison", &[("max_stacks", 2.0)])).unwrap();
+        assert_eq!(poison.stack, StatusStack::Stack { max_stacks: 2 });
+        assert_eq!(poison.value, 1.0);
+
+        assert!(create_status_effect(&effect_data("unknown", &[])).is_none());
+    }
+
+    #[test]
+    fn test_tick_damage() {
+        let owner = Entity::PLACEHOLDER;
+        let caster = Entity::from_raw_u32(1).unwrap();
+
+        let event = tick_damage(
+            &StatusEffectTick {
+                owner,
+                caster: Some(caster),
+                value: 1.5,
+                stacks: 2,
+            },
+            DamageKind::True,
+        );
+
+        assert_eq!(event.source, caster);
+        assert_eq!(event.target, owner);
+        assert_eq!(event.amount, 3);
+    }
+}
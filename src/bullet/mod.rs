@@ -1,10 +1,18 @@
 use avian2d::prelude::*;
 use bevy::{platform::collections::HashSet, prelude::*};
 
-use crate::common::{GameLayer, Stas, spawn_hit};
+use crate::{
+    common::{DamageEvent, DamageKind, DamageSystems, GameLayer, Health, spawn_hit},
+    screens::Screen,
+};
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(Update, (bullet_attack, queue_free).chain());
+    app.add_systems(
+        Update,
+        (bullet_attack, queue_free)
+            .chain()
+            .before(DamageSystems::Apply),
+    );
 }
 
 #[derive(Debug, Component)]
@@ -25,20 +33,28 @@ fn queue_free(
 }
 
 fn on_bullet_attack(
-    stats_q: &mut Query<&mut Stas>,
+    stats_q: &Query<(), With<Health>>,
     bullet_q: &Query<(&Bullet, Entity)>,
     bullet_entity: Entity,
     stats_entity: Entity,
     die_set: &mut HashSet<Entity>,
+    writer: &mut MessageWriter<DamageEvent>,
 ) {
-    if let Ok((_bullet, entity)) = bullet_q.get(bullet_entity) {
-        tracing::info!("bullet attack start");
-
-        if let Ok(mut stats) = stats_q.get_mut(stats_entity) {
-            stats.update_health(-5);
-            if stats.is_die() {
-                die_set.insert(stats_entity);
-            }
+    //子弹只生效一次
+    if die_set.contains(&bullet_entity) {
+        return;
+    }
+
+    if let Ok((bullet, entity)) = bullet_q.get(bullet_entity) {
+        tracing::debug!("bullet attack start");
+
+        if stats_q.contains(stats_entity) {
+            writer.write(DamageEvent {
+                source: bullet.source.unwrap_or(entity),
+                target: stats_entity,
+                amount: bullet.damage,
+                kind: DamageKind::Physical,
+            });
         }
         die_set.insert(entity);
     }
@@ -48,7 +64,8 @@ fn bullet_attack(
     mut commands: Commands,
     mut collision_reader: MessageReader<CollisionStart>,
     bullet_q: Query<(&Bullet, Entity)>,
-    mut stats_q: Query<&mut Stas>,
+    stats_q: Query<(), With<Health>>,
+    mut writer: MessageWriter<DamageEvent>,
 ) {
     let mut die_set = HashSet::new();
 
@@ -59,8 +76,8 @@ fn bullet_attack(
         let body1 = event.body1.clone().unwrap();
         let body2 = event.body2.clone().unwrap();
 
-        on_bullet_attack(&mut stats_q, &bullet_q, body1, body2, &mut die_set);
-        on_bullet_attack(&mut stats_q, &bullet_q, body2, body1, &mut die_set);
+        on_bullet_attack(&stats_q, &bullet_q, body1, body2, &mut die_set, &mut writer);
+        on_bullet_attack(&stats_q, &bullet_q, body2, body1, &mut die_set, &mut writer);
     }
 
     for entity in die_set.iter() {
@@ -69,13 +86,19 @@ fn bullet_attack(
 }
 
 #[derive(Debug, Component)]
-pub struct Bullet;
+pub struct Bullet {
+    //发射子弹的单位
+    pub source: Option<Entity>,
+    pub damage: i32,
+}
 
 pub fn spawn_bullet(
     commands: &mut Commands,
     asset_server: &AssetServer,
+    source: Option<Entity>,
+    damage: i32,
     layers: CollisionLayers,
-    direction: Vec2,
+    velocity: Vec2,
     bullet_position: Vec2,
 ) {
     let image = asset_server.load("images/bullet/ball.png");
@@ -83,17 +106,18 @@ pub fn spawn_bullet(
     let collider = Collider::circle(3.0);
 
     let mut commands = commands.spawn((
-        Bullet,
+        Bullet { source, damage },
         Sprite { image, ..default() },
         RigidBody::Kinematic,
         collider.clone(),
         GameLayer::default_layers(),
-        LinearVelocity(direction * 400.0 * 1.0),
+        LinearVelocity(velocity),
         Transform {
             translation: Vec3::new(bullet_position.x, bullet_position.y, 0.0),
             ..default()
         },
         StartPosition(bullet_position),
+        DespawnOnExit(Screen::Gameplay),
     ));
 
     spawn_hit(&mut commands, collider, layers);
@@ -277,7 +277,7 @@ fn on_clamp_bounds(
     mut query: Query<(&PanCam, &mut Transform, &Projection)>,
 ) {
     if let Ok((pan_cam, mut transform, Projection::Orthographic(projection))) =
-        query.get_mut(event.observer())
+        query.get_mut(event.entity)
     {
         if !pan_cam.enabled {
             return;
@@ -1,11 +1,156 @@
 use avian2d::prelude::*;
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
 
-use crate::screens::Screen;
+use crate::{
+    common::{Health, LightSource, RANGE},
+    screens::Screen,
+    skill::SkillAttributeSet,
+};
 
+///攻击范围内的敌人，按照进入的顺序排列
 #[derive(Debug, Component, Default)]
 pub struct EnemyTargets(pub Vec<Entity>);
 
+///选择攻击目标的策略
+#[derive(
+    Debug, Component, Clone, Copy, PartialEq, Eq, Default, Reflect, Deserialize, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetingStrategy {
+    ///最先进入攻击范围的敌人
+    #[default]
+    First,
+    ///最后进入攻击范围的敌人
+    Last,
+    ///距离最近的敌人
+    Closest,
+    ///生命值最高的敌人
+    Strongest,
+    ///生命值最低的敌人
+    Weakest,
+    ///距离光源最近的敌人
+    ClosestToLight,
+}
+
+impl TargetingStrategy {
+    ///单位文件中的名称，与序列化的名称一致
+    pub fn name(&self) -> &'static str {
+        match self {
+            TargetingStrategy::First => "first",
+            TargetingStrategy::Last => "last",
+            TargetingStrategy::Closest => "closest",
+            TargetingStrategy::Strongest => "strongest",
+            TargetingStrategy::Weakest => "weakest",
+            TargetingStrategy::ClosestToLight => "closest_to_light",
+        }
+    }
+
+    ///界面上显示的名称，从单位文件中的名称得到
+    pub fn label(&self) -> String {
+        self.name().replace('_', " ")
+    }
+
+    ///切换到下一个策略
+    pub fn next(&self) -> Self {
+        match self {
+            TargetingStrategy::First => TargetingStrategy::Last,
+            TargetingStrategy::Last => TargetingStrategy::Closest,
+            TargetingStrategy::Closest => TargetingStrategy::Strongest,
+            TargetingStrategy::Strongest => TargetingStrategy::Weakest,
+            TargetingStrategy::Weakest => TargetingStrategy::ClosestToLight,
+            TargetingStrategy::ClosestToLight => TargetingStrategy::First,
+        }
+    }
+
+    ///从候选目标中选择，候选目标按照进入攻击范围的顺序排列
+    pub fn select(
+        &self,
+        origin: Vec2,
+        light_sources: &[Vec2],
+        candidates: &[TargetCandidate],
+    ) -> Option<Entity> {
+        let candidate = match self {
+            TargetingStrategy::First => candidates.first(),
+            TargetingStrategy::Last => candidates.last(),
+            TargetingStrategy::Closest => candidates.iter().min_by(|a, b| {
+                a.position
+                    .distance_squared(origin)
+                    .total_cmp(&b.position.distance_squared(origin))
+            }),
+            TargetingStrategy::Strongest => candidates.iter().max_by_key(|item| item.health),
+            TargetingStrategy::Weakest => candidates.iter().min_by_key(|item| item.health),
+            TargetingStrategy::ClosestToLight => {
+                if light_sources.is_empty() {
+                    candidates.first()
+                } else {
+                    candidates.iter().min_by(|a, b| {
+                        a.distance_to_nearest(light_sources)
+                            .total_cmp(&b.distance_to_nearest(light_sources))
+                    })
+                }
+            }
+        };
+
+        candidate.map(|item| item.entity)
+    }
+}
+
+///候选的攻击目标
+#[derive(Debug, Clone)]
+pub struct TargetCandidate {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub health: i32,
+}
+
+impl TargetCandidate {
+    fn distance_to_nearest(&self, positions: &[Vec2]) -> f32 {
+        positions
+            .iter()
+            .map(|position| self.position.distance_squared(*position))
+            .fold(f32::INFINITY, f32::min)
+    }
+}
+
+#[derive(SystemParam)]
+pub struct TargetSelector<'w, 's> {
+    target_q: Query<'w, 's, (&'static GlobalTransform, Option<&'static Health>)>,
+    light_source_q: Query<'w, 's, &'static GlobalTransform, With<LightSource>>,
+}
+
+impl TargetSelector<'_, '_> {
+    ///按照策略从攻击范围内的敌人中选择目标
+    pub fn select(
+        &self,
+        strategy: TargetingStrategy,
+        origin: Vec2,
+        enemy_targets: &EnemyTargets,
+    ) -> Option<Entity> {
+        let candidates = enemy_targets
+            .0
+            .iter()
+            .filter_map(|entity| {
+                let (transform, health) = self.target_q.get(*entity).ok()?;
+
+                Some(TargetCandidate {
+                    entity: *entity,
+                    position: transform.translation().truncate(),
+                    health: health.map(|health| health.value).unwrap_or_default(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let light_sources = self
+            .light_source_q
+            .iter()
+            .map(|transform| transform.translation().truncate())
+            .collect::<Vec<_>>();
+
+        strategy.select(origin, &light_sources, &candidates)
+    }
+}
+
 #[derive(Debug, Component)]
 pub struct AttackDistance;
 
@@ -25,6 +170,24 @@ pub fn spawn_attack_distance(
         .id()
 }
 
+//攻击范围的属性变化时更新攻击范围的大小
+fn update_attack_distance(
+    owner_q: Query<(&SkillAttributeSet, &Children), Changed<SkillAttributeSet>>,
+    mut attack_distance_q: Query<&mut Collider, With<AttackDistance>>,
+) {
+    for (attribute_set, children) in owner_q.iter() {
+        let Some(range) = attribute_set.get_current_value(RANGE) else {
+            continue;
+        };
+
+        for child in children {
+            if let Ok(mut collider) = attack_distance_q.get_mut(*child) {
+                *collider = Collider::circle(range.max(0) as f32);
+            }
+        }
+    }
+}
+
 //检测敌人是否存在
 fn check_enemy_targets(mut enemy_targets_q: Query<&mut EnemyTargets>, enemy_q: Query<Entity>) {
     for mut enemy_targets in enemy_targets_q.iter_mut() {
@@ -143,9 +306,35 @@ pub enum AttackDistanceSets {
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(
         Update,
-        (check_enemy_targets, record_enemy)
+        (update_attack_distance, check_enemy_targets, record_enemy)
             .chain()
             .in_set(AttackDistanceSets::Actions)
             .run_if(in_state(Screen::Gameplay)),
     );
 }
+
+#[cfg(test)]
+mod test {
+    use super::TargetingStrategy;
+
+    #[test]
+    fn test_targeting_strategy_name() {
+        let mut strategy = TargetingStrategy::First;
+
+        //所有策略的名称都可以从单位文件中读取
+        loop {
+            let parsed: TargetingStrategy = yaml_serde::from_str(strategy.name()).unwrap();
+            assert_eq!(parsed, strategy);
+
+            strategy = strategy.next();
+            if strategy == TargetingStrategy::First {
+                break;
+            }
+        }
+
+        assert_eq!(
+            TargetingStrategy::ClosestToLight.label(),
+            "closest to light"
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use bevy::{platform::collections::HashSet, prelude::*};
+
+use crate::{
+    common::{ARMOR, DAMAGE_TAKEN, Health, MAGIC_RESISTANCE, get_max_health},
+    skill::SkillAttributeSet,
+};
+
+///伤害类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DamageKind {
+    ///物理伤害，受护甲影响
+    #[default]
+    Physical,
+    ///魔法伤害，受魔抗影响
+    Magic,
+    ///真实伤害，不受任何减免
+    True,
+}
+
+///造成伤害
+#[derive(Debug, Clone, Message)]
+pub struct DamageEvent {
+    pub source: Entity,
+    pub target: Entity,
+    pub amount: i32,
+    pub kind: DamageKind,
+}
+
+///死亡，在实体被删除前发送
+#[derive(Debug, Clone, Message)]
+pub struct Died {
+    pub entity: Entity,
+    pub killer: Entity,
+}
+
+///抗性
+#[derive(Debug, Clone, Default)]
+pub struct Resistances {
+    //护甲，按照数值减少物理伤害
+    pub armor: i32,
+    //魔抗，按照百分比减少魔法伤害
+    pub magic_resistance: i32,
+}
+
+impl Resistances {
+    pub fn from_attribute_set(attribute_set: &SkillAttributeSet) -> Self {
+        Resistances {
+            armor: attribute_set.get_current_value(ARMOR).unwrap_or_default(),
+            magic_resistance: attribute_set
+                .get_current_value(MAGIC_RESISTANCE)
+                .unwrap_or_default(),
+        }
+    }
+
+    ///计算减免后的伤害
+    pub fn reduce(&self, amount: i32, kind: DamageKind) -> i32 {
+        if amount <= 0 {
+            return 0;
+        }
+
+        match kind {
+            DamageKind::Physical => (amount - self.armor).max(1),
+            DamageKind::Magic => {
+                let resistance = self.magic_resistance.clamp(0, 100);
+                amount * (100 - resistance) / 100
+            }
+            DamageKind::True => amount,
+        }
+    }
+}
+
+///按照受到的伤害增加的百分比调整伤害
+pub fn amplify_damage(amount: i32, damage_taken: i32) -> i32 {
+    amount * (100 + damage_taken).max(0) / 100
+}
+
+#[derive(SystemSet, Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum DamageSystems {
+    ///结算伤害
+    Apply,
+    ///处理死亡，例如奖励和特效
+    Died,
+    ///删除死亡的实体
+    Despawn,
+}
+
+//结算伤害
+fn apply_damage(
+    mut reader: MessageReader<DamageEvent>,
+    mut writer: MessageWriter<Died>,
+    mut health_q: Query<(&mut Health, &SkillAttributeSet)>,
+) {
+    let mut die_set = HashSet::new();
+
+    for event in reader.read() {
+        if die_set.contains(&event.target) {
+            continue;
+        }
+
+        let Ok((mut health, attribute_set)) = health_q.get_mut(event.target) else {
+            continue;
+        };
+
+        let amount =
+            Resistances::from_attribute_set(attribute_set).reduce(event.amount, event.kind);
+        let amount = amplify_damage(
+            amount,
+            attribute_set
+                .get_current_value(DAMAGE_TAKEN)
+                .unwrap_or_default(),
+        );
+
+        health.update(-amount, get_max_health(attribute_set));
+
+        if health.is_die() {
+            die_set.insert(event.target);
+
+            writer.write(Died {
+                entity: event.target,
+                killer: event.source,
+            });
+        }
+    }
+}
+
+//最大生命值降低时限制当前的生命值
+fn clamp_health(
+    mut health_q: Query<(&mut Health, &SkillAttributeSet), Changed<SkillAttributeSet>>,
+) {
+    for (mut health, attribute_set) in health_q.iter_mut() {
+        let max_health = get_max_health(attribute_set);
+
+        if health.value > max_health {
+            health.update(0, max_health);
+        }
+    }
+}
+
+fn despawn_died(mut commands: Commands, mut reader: MessageReader<Died>) {
+    for event in reader.read() {
+        if let Ok(mut entity_commands) = commands.get_entity(event.entity) {
+            entity_commands.despawn();
+        }
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_message::<DamageEvent>();
+    app.add_message::<Died>();
+
+    app.configure_sets(
+        Update,
+        (
+            DamageSystems::Apply,
+            DamageSystems::Died,
+            DamageSystems::Despawn,
+        )
+            .chain(),
+    );
+
+    app.add_systems(
+        Update,
+        (clamp_health, apply_damage)
+            .chain()
+            .in_set(DamageSystems::Apply),
+    );
+    app.add_systems(Update, despawn_died.in_set(DamageSystems::Despawn));
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DamageKind, Resistances, amplify_damage};
+
+    #[test]
+    fn test_resistances() {
+        let resistances = Resistances {
+            armor: 2,
+            magic_resistance: 50,
+        };
+
+        assert_eq!(resistances.reduce(5, DamageKind::Physical), 3);
+        assert_eq!(resistances.reduce(1, DamageKind::Physical), 1);
+        assert_eq!(resistances.reduce(5, DamageKind::Magic), 2);
+        assert_eq!(resistances.reduce(5, DamageKind::True), 5);
+        assert_eq!(resistances.reduce(0, DamageKind::True), 0);
+    }
+
+    #[test]
+    fn test_amplify_damage() {
+        assert_eq!(amplify_damage(10, 0), 10);
+        assert_eq!(amplify_damage(10, 20), 12);
+        assert_eq!(amplify_damage(10, -200), 0);
+    }
+}
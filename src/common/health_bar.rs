@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+
+use crate::{
+    common::{DamageSystems, Health, get_max_health},
+    enemy::Enemy,
+    skill::SkillAttributeSet,
+};
+
+const HEALTH_BAR_WIDTH: f32 = 64.0;
+const HEALTH_BAR_HEIGHT: f32 = 8.0;
+//血条和实体图片之间的距离
+const HEALTH_BAR_MARGIN: f32 = 8.0;
+const HEALTH_BAR_LAYER: f32 = 10.0;
+
+const HEALTH_BAR_BACKGROUND_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+const UNIT_HEALTH_BAR_COLOR: Color = Color::srgb(0.2, 0.8, 0.2);
+const ENEMY_HEALTH_BAR_COLOR: Color = Color::srgb(0.9, 0.2, 0.2);
+
+///血条的背景，满血时隐藏
+#[derive(Debug, Component)]
+pub struct HealthBar;
+
+///血条的填充部分
+#[derive(Debug, Component)]
+pub struct HealthBarFill;
+
+///实体的血条
+#[derive(Debug, Component)]
+pub struct HealthBarEntity {
+    bar: Entity,
+    fill: Entity,
+}
+
+//为拥有生命值的实体添加血条
+fn spawn_health_bar(
+    mut commands: Commands,
+    stats_q: Query<(Entity, Option<&Sprite>, Has<Enemy>), Added<Health>>,
+) {
+    for (entity, sprite, is_enemy) in stats_q.iter() {
+        let height = sprite
+            .and_then(|sprite| sprite.custom_size)
+            .map(|size| size.y)
+            .unwrap_or_default();
+
+        let color = if is_enemy {
+            ENEMY_HEALTH_BAR_COLOR
+        } else {
+            UNIT_HEALTH_BAR_COLOR
+        };
+
+        let fill = commands
+            .spawn((
+                HealthBarFill,
+                Sprite::from_color(color, Vec2::new(HEALTH_BAR_WIDTH, HEALTH_BAR_HEIGHT)),
+                Transform::from_xyz(0.0, 0.0, 0.1),
+            ))
+            .id();
+
+        let bar = commands
+            .spawn((
+                HealthBar,
+                Name::new("HealthBar"),
+                Sprite::from_color(
+                    HEALTH_BAR_BACKGROUND_COLOR,
+                    Vec2::new(HEALTH_BAR_WIDTH, HEALTH_BAR_HEIGHT),
+                ),
+                Transform::from_xyz(0.0, height / 2.0 + HEALTH_BAR_MARGIN, HEALTH_BAR_LAYER),
+                Visibility::Hidden,
+            ))
+            .add_child(fill)
+            .id();
+
+        commands
+            .entity(entity)
+            .add_child(bar)
+            .insert(HealthBarEntity { bar, fill });
+    }
+}
+
+//生命值或者最大生命值变化时更新血条
+fn update_health_bar(
+    health_q: Query<
+        (&Health, &SkillAttributeSet, &HealthBarEntity),
+        Or<(Changed<Health>, Changed<SkillAttributeSet>)>,
+    >,
+    mut bar_q: Query<&mut Visibility, With<HealthBar>>,
+    mut fill_q: Query<&mut Transform, With<HealthBarFill>>,
+) {
+    for (health, attribute_set, health_bar) in health_q.iter() {
+        let max_health = get_max_health(attribute_set);
+
+        if let Ok(mut visibility) = bar_q.get_mut(health_bar.bar) {
+            *visibility = if health.value >= max_health {
+                Visibility::Hidden
+            } else {
+                Visibility::Inherited
+            };
+        }
+
+        if let Ok(mut transform) = fill_q.get_mut(health_bar.fill) {
+            let ratio = health.ratio(max_health);
+
+            //从右向左减少
+            transform.scale.x = ratio;
+            transform.translation.x = -(1.0 - ratio) * HEALTH_BAR_WIDTH / 2.0;
+        }
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (spawn_health_bar, update_health_bar)
+            .chain()
+            .after(DamageSystems::Apply),
+    );
+}
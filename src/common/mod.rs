@@ -1,4 +1,6 @@
 mod attack_distance;
+mod damage;
+mod health_bar;
 mod hit_hurt;
 mod progress_bar;
 mod stas;
@@ -6,6 +8,8 @@ mod state_chart;
 mod sunlight;
 
 pub use attack_distance::*;
+pub use damage::*;
+pub use health_bar::*;
 pub use hit_hurt::*;
 pub use progress_bar::*;
 pub use stas::*;
@@ -29,7 +33,16 @@ pub enum GameLayer {
 
 // 光源
 #[derive(Debug, Component)]
-pub struct LightSource;
+pub struct LightSource {
+    //亮度，影响敌人对光源的选择
+    pub brightness: f32,
+}
+
+impl Default for LightSource {
+    fn default() -> Self {
+        LightSource { brightness: 1.0 }
+    }
+}
 
 impl GameLayer {
     pub fn default_layers() -> CollisionLayers {
@@ -77,6 +90,8 @@ impl GameLayer {
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
         attack_distance::plugin,
+        damage::plugin,
+        health_bar::plugin,
         StateChartConfigPlugin,
         ProgressBarPlugin,
     ));
@@ -1,27 +1,135 @@
 use bevy::ecs::component::Component;
 
+use crate::skill::SkillAttributeSet;
+
+///最大生命值的属性名
+pub const MAX_HEALTH: &str = "max_health";
+///护甲的属性名，按照数值减少物理伤害
+pub const ARMOR: &str = "armor";
+///魔抗的属性名，按照百分比减少魔法伤害
+pub const MAGIC_RESISTANCE: &str = "magic_resistance";
+///攻击伤害的属性名
+pub const ATTACK_DAMAGE: &str = "attack_damage";
+///攻击速度的属性名，100为正常速度
+pub const ATTACK_SPEED: &str = "attack_speed";
+///移动速度的属性名，单位为像素每秒
+pub const MOVE_SPEED: &str = "move_speed";
+///攻击范围的属性名
+pub const RANGE: &str = "range";
+///受到的伤害增加的百分比的属性名
+pub const DAMAGE_TAKEN: &str = "damage_taken";
+
+///正常的攻击速度
+pub const DEFAULT_ATTACK_SPEED: f32 = 100.0;
+
+///战斗属性的基础值
+#[derive(Debug, Clone)]
+pub struct CombatStats {
+    pub max_health: f32,
+    pub armor: f32,
+    pub magic_resistance: f32,
+    pub attack_damage: f32,
+    pub attack_speed: f32,
+    pub move_speed: f32,
+    pub range: f32,
+}
+
+impl Default for CombatStats {
+    fn default() -> Self {
+        Self {
+            max_health: 20.0,
+            armor: 0.0,
+            magic_resistance: 0.0,
+            attack_damage: 0.0,
+            attack_speed: DEFAULT_ATTACK_SPEED,
+            move_speed: 0.0,
+            range: 0.0,
+        }
+    }
+}
+
+impl CombatStats {
+    ///创建包含所有战斗属性的属性集
+    pub fn to_attribute_set(&self) -> SkillAttributeSet {
+        SkillAttributeSet::from_base_values(&[
+            (MAX_HEALTH, self.max_health),
+            (ARMOR, self.armor),
+            (MAGIC_RESISTANCE, self.magic_resistance),
+            (ATTACK_DAMAGE, self.attack_damage),
+            (ATTACK_SPEED, self.attack_speed),
+            (MOVE_SPEED, self.move_speed),
+            (RANGE, self.range),
+            (DAMAGE_TAKEN, 0.0),
+        ])
+    }
+}
+
+///按照攻击速度缩放经过的时间
+pub fn scale_by_attack_speed(secs: f32, attack_speed: i32) -> f32 {
+    secs * attack_speed.max(0) as f32 / DEFAULT_ATTACK_SPEED
+}
+
+///当前的生命值，上限由属性max_health决定
 #[derive(Debug, Component)]
-pub struct Stas {
-    pub health: i32,
-    pub health_max: i32,
+pub struct Health {
+    pub value: i32,
 }
 
-impl Stas {
-    pub fn update_health(&mut self, value: i32) {
-        self.health += value;
-        self.health = self.health.clamp(0, self.health_max);
+impl Health {
+    pub fn new(value: i32) -> Self {
+        Health { value }
+    }
+
+    pub fn update(&mut self, value: i32, max_health: i32) {
+        self.value += value;
+        self.value = self.value.clamp(0, max_health.max(0));
     }
 
     pub fn is_die(&self) -> bool {
-        self.health == 0
+        self.value == 0
     }
-}
 
-impl Default for Stas {
-    fn default() -> Self {
-        Self {
-            health: 20,
-            health_max: 20,
+    ///生命值的比例
+    pub fn ratio(&self, max_health: i32) -> f32 {
+        if max_health > 0 {
+            (self.value as f32 / max_health as f32).clamp(0.0, 1.0)
+        } else {
+            0.0
         }
     }
 }
+
+///属性集中的最大生命值
+pub fn get_max_health(attribute_set: &SkillAttributeSet) -> i32 {
+    attribute_set
+        .get_current_value(MAX_HEALTH)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_health() {
+        let attribute_set = CombatStats::default().to_attribute_set();
+        let max_health = get_max_health(&attribute_set);
+        let mut health = Health::new(max_health);
+
+        health.update(5, max_health);
+        assert_eq!(health.value, 20);
+
+        health.update(-15, max_health);
+        assert_eq!(health.ratio(max_health), 0.25);
+
+        health.update(-10, max_health);
+        assert!(health.is_die());
+    }
+
+    #[test]
+    fn test_scale_by_attack_speed() {
+        assert_eq!(scale_by_attack_speed(1.0, 100), 1.0);
+        assert_eq!(scale_by_attack_speed(1.0, 150), 1.5);
+        assert_eq!(scale_by_attack_speed(1.0, -10), 0.0);
+    }
+}
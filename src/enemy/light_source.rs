@@ -0,0 +1,182 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use vleue_navigator::{NavMesh, prelude::ManagedNavMesh};
+
+use crate::{common::LightSource, enemy::Enemy};
+
+///敌人选择光源的策略
+#[derive(
+    Debug, Component, Clone, Copy, PartialEq, Eq, Default, Reflect, Deserialize, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LightSourcePolicy {
+    ///直线距离最近的光源
+    #[default]
+    Nearest,
+    ///亮度最高的光源，亮度相同时选择最近的
+    Brightest,
+    ///按照亮度和距离随机选择，越亮越近的光源越容易被选中
+    RandomWeighted,
+    ///沿着导航网格路径最近的光源
+    PathCost,
+}
+
+///候选的光源
+#[derive(Debug, Clone)]
+pub struct LightSourceCandidate {
+    pub entity: Entity,
+    pub position: Vec3,
+    pub brightness: f32,
+    //沿着导航网格的路径长度，无法到达时为None
+    pub path_cost: Option<f32>,
+}
+
+impl LightSourcePolicy {
+    ///从候选光源中选择，`random`为`[0, 1)`之间的随机数
+    pub fn select(
+        &self,
+        origin: Vec3,
+        candidates: &[LightSourceCandidate],
+        random: f32,
+    ) -> Option<Entity> {
+        let distance = |candidate: &LightSourceCandidate| candidate.position.distance(origin);
+
+        let candidate = match self {
+            LightSourcePolicy::Nearest => candidates
+                .iter()
+                .min_by(|a, b| distance(a).total_cmp(&distance(b))),
+            LightSourcePolicy::Brightest => candidates.iter().min_by(|a, b| {
+                b.brightness
+                    .total_cmp(&a.brightness)
+                    .then(distance(a).total_cmp(&distance(b)))
+            }),
+            LightSourcePolicy::RandomWeighted => {
+                let weights = candidates
+                    .iter()
+                    .map(|candidate| candidate.brightness.max(0.0) / distance(candidate).max(1.0))
+                    .collect::<Vec<_>>();
+
+                let total: f32 = weights.iter().sum();
+
+                if total <= 0.0 {
+                    candidates.first()
+                } else {
+                    let mut value = random * total;
+                    let mut selected = candidates.last();
+
+                    for (candidate, weight) in candidates.iter().zip(weights.iter()) {
+                        if value < *weight {
+                            selected = Some(candidate);
+                            break;
+                        }
+
+                        value -= weight;
+                    }
+
+                    selected
+                }
+            }
+            //无法到达的光源排在最后
+            LightSourcePolicy::PathCost => candidates.iter().min_by(|a, b| {
+                a.path_cost
+                    .unwrap_or(f32::INFINITY)
+                    .total_cmp(&b.path_cost.unwrap_or(f32::INFINITY))
+                    .then(distance(a).total_cmp(&distance(b)))
+            }),
+        };
+
+        candidate.map(|candidate| candidate.entity)
+    }
+}
+
+///敌人要前往的光源
+#[derive(Debug, Component)]
+pub struct LightSourcePosition {
+    pub entity: Entity,
+    pub position: Vec3,
+}
+
+//寻找光源，光源被摧毁或者放置新的光源时重新选择
+pub fn find_light_source(
+    mut commands: Commands,
+    enemy_query: Query<
+        (
+            &GlobalTransform,
+            Entity,
+            Option<&LightSourcePolicy>,
+            Option<&LightSourcePosition>,
+        ),
+        With<Enemy>,
+    >,
+    light_source_q: Query<(&GlobalTransform, &LightSource, Entity)>,
+    //新放置的光源在变换传播后位置才正确
+    changed_light_source_q: Query<(), (With<LightSource>, Changed<GlobalTransform>)>,
+    mut removed_light_source: RemovedComponents<LightSource>,
+    navmeshes: Res<Assets<NavMesh>>,
+    navmesh: Option<Single<&ManagedNavMesh>>,
+) {
+    let light_source_changed =
+        !changed_light_source_q.is_empty() || removed_light_source.read().count() > 0;
+
+    let navmesh = navmesh.and_then(|navmesh| navmeshes.get(*navmesh));
+
+    for (transform, entity, policy, light_source_position) in enemy_query.iter() {
+        if light_source_q.is_empty() {
+            if light_source_position.is_some() {
+                commands.entity(entity).remove::<LightSourcePosition>();
+            }
+            continue;
+        }
+
+        let target_exists = light_source_position
+            .map(|position| light_source_q.contains(position.entity))
+            .unwrap_or(false);
+
+        if target_exists && !light_source_changed {
+            continue;
+        }
+
+        let policy = policy.copied().unwrap_or_default();
+        let origin = transform.translation();
+
+        let candidates = light_source_q
+            .iter()
+            .map(
+                |(light_source_transform, light_source, light_source_entity)| {
+                    let position = light_source_transform.translation();
+
+                    let path_cost = if policy == LightSourcePolicy::PathCost {
+                        navmesh
+                            .and_then(|navmesh| navmesh.transformed_path(origin, position))
+                            .map(|path| path.length)
+                    } else {
+                        None
+                    };
+
+                    LightSourceCandidate {
+                        entity: light_source_entity,
+                        position,
+                        brightness: light_source.brightness,
+                        path_cost,
+                    }
+                },
+            )
+            .collect::<Vec<_>>();
+
+        let Some(selected) = policy.select(origin, &candidates, rand::random::<f32>()) else {
+            continue;
+        };
+
+        let Some(candidate) = candidates
+            .iter()
+            .find(|candidate| candidate.entity == selected)
+        else {
+            continue;
+        };
+
+        commands.entity(entity).insert(LightSourcePosition {
+            entity: candidate.entity,
+            position: candidate.position,
+        });
+    }
+}
@@ -1,70 +1,293 @@
-use std::fmt::Debug;
+mod light_source;
+
+pub use light_source::*;
+
+use std::{fmt::Debug, time::Duration};
 
 use crate::{
-    common::{GameLayer, Hitbox, LightSource, Stas, spawn_hit, spawn_hurt},
-    navigator::NavigatorPath,
+    asset_tracking::LoadResource,
+    common::{
+        ATTACK_DAMAGE, ATTACK_SPEED, CombatStats, DamageEvent, DamageKind, DamageSystems, Died,
+        GameLayer, Health, Hitbox, MAX_HEALTH, MOVE_SPEED, scale_by_attack_speed, spawn_hit,
+        spawn_hurt,
+    },
+    map::{get_item_position, get_position_i},
+    navigator::{
+        FlowFields, NavigatorMode, NavigatorPath, NavigatorPathBudget, NavigatorPathRequest,
+        NavigatorSteering, SeparationGrid,
+    },
+    product::ProductMeta,
+    skill::SkillAttributeSet,
 };
 use avian2d::prelude::*;
 use bevy::{
+    asset::{AssetLoader, AsyncReadExt, LoadContext, io::Reader},
     ecs::system::SystemParam,
-    platform::collections::{HashMap, HashSet},
+    platform::collections::HashMap,
     prelude::*,
 };
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use vleue_navigator::{NavMesh, prelude::ManagedNavMesh};
 
+#[derive(SystemParam)]
+pub struct EnemySystemParams<'w> {
+    enemy_data_assets: Res<'w, EnemyDataAssets>,
+    enemy_data_set: Res<'w, Assets<EnemyData>>,
+}
+
+impl EnemySystemParams<'_> {
+    pub fn get_enemy_data(&self, item_name: &str) -> Option<EnemyData> {
+        self.enemy_data_assets
+            .assets
+            .get(item_name)
+            .and_then(|handle| self.enemy_data_set.get(handle).cloned())
+    }
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct EnemyDataAssets {
+    assets: HashMap<String, Handle<EnemyData>>,
+}
+
+impl FromWorld for EnemyDataAssets {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let mut assets = HashMap::new();
+
+        assets.insert(
+            "square".into(),
+            asset_server.load("enemy/square.enemy_data.yaml"),
+        );
+
+        assets.insert(
+            "small_square".into(),
+            asset_server.load("enemy/small_square.enemy_data.yaml"),
+        );
+
+        Self { assets }
+    }
+}
+
+#[derive(Default, TypePath)]
+pub struct EnemyDataLoader;
+
+#[derive(Debug, Error)]
+pub enum EnemyDataLoaderError {
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse Yaml: {0}")]
+    Yaml(#[from] yaml_serde::Error),
+}
+
+impl AssetLoader for EnemyDataLoader {
+    type Asset = EnemyData;
+
+    type Settings = ();
+
+    type Error = EnemyDataLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut buffer = String::new();
+        reader.read_to_string(&mut buffer).await?;
+        let asset: EnemyData = yaml_serde::from_str(&buffer)?;
+        Ok(asset)
+    }
+    fn extensions(&self) -> &[&str] {
+        &[".enemy_data.yaml"]
+    }
+}
+
+#[derive(Debug, Clone, Asset, Reflect, Deserialize, Serialize)]
+pub struct EnemyData {
+    pub item_name: String,
+    pub image: String,
+    pub health: i32,
+    //移动速度，单位为像素每秒
+    pub speed: f32,
+    //碰撞体和图片的边长
+    pub size: f32,
+    //每次攻击造成的伤害
+    pub damage: i32,
+    //攻击冷却，单位为秒
+    pub attack_cooldown: f32,
+    //被击杀后奖励的阳光
+    pub reward: u32,
+    //护甲，按照数值减少物理伤害
+    #[serde(default)]
+    pub armor: i32,
+    //魔抗，按照百分比减少魔法伤害
+    #[serde(default)]
+    pub magic_resistance: i32,
+    //选择光源的策略
+    #[serde(default)]
+    pub light_source_policy: LightSourcePolicy,
+}
+
+impl EnemyData {
+    pub fn get_combat_stats(&self) -> CombatStats {
+        CombatStats {
+            max_health: self.health as f32,
+            armor: self.armor as f32,
+            magic_resistance: self.magic_resistance as f32,
+            attack_damage: self.damage as f32,
+            move_speed: self.speed,
+            ..default()
+        }
+    }
+
+    pub fn get_enemy_image(&self, asset_server: &AssetServer) -> Handle<Image> {
+        asset_server.load(&format!("images/enemy/{}.png", self.image))
+    }
+}
+
 #[derive(SystemParam)]
 pub struct EnemyAttackSystemParam<'w, 's> {
-    commands: Commands<'w, 's>,
-    enemy_q: Query<'w, 's, (&'static Enemy, Entity)>,
-    stats_q: Query<'w, 's, &'static mut Stas, Without<Enemy>>,
+    enemy_q: Query<'w, 's, &'static mut EnemyAttack>,
     hitbox_q: Query<'w, 's, &'static Hitbox>,
 }
 
 impl EnemyAttackSystemParam<'_, '_> {
-    fn apply(&mut self, enemy_entity: Entity, stats_entity: Entity, die_set: &mut HashSet<Entity>) {
-        if let Ok((_enemy, _entity)) = self.enemy_q.get(enemy_entity) {
-            tracing::info!("enemy attack start");
-
-            if let Ok(mut stats) = self.stats_q.get_mut(stats_entity) {
-                stats.update_health(-5);
-                if stats.is_die() {
-                    die_set.insert(stats_entity);
-                }
-            }
-        }
-    }
-    fn handle_collision_start(&self, event: &CollisionStart) -> Option<(Entity, Entity)> {
-        if event.body1.is_none() || event.body2.is_none() {
+    fn handle_collision(
+        &self,
+        collider1: Entity,
+        collider2: Entity,
+        body1: Option<Entity>,
+        body2: Option<Entity>,
+    ) -> Option<(Entity, Entity)> {
+        if body1.is_none() || body2.is_none() {
             return None;
         }
 
-        if self.hitbox_q.contains(event.collider1) && self.enemy_q.contains(event.body1.unwrap()) {
-            return Some((event.body1.unwrap(), event.body2.unwrap()));
+        if self.hitbox_q.contains(collider1) && self.enemy_q.contains(body1.unwrap()) {
+            return Some((body1.unwrap(), body2.unwrap()));
         }
 
-        if self.hitbox_q.contains(event.collider2) && self.enemy_q.contains(event.body2.unwrap()) {
-            return Some((event.body2.unwrap(), event.body1.unwrap()));
+        if self.hitbox_q.contains(collider2) && self.enemy_q.contains(body2.unwrap()) {
+            return Some((body2.unwrap(), body1.unwrap()));
         }
 
         return None;
     }
 }
 
+//记录敌人接触到的目标
+fn update_enemy_attack_targets(
+    mut collision_start_reader: MessageReader<CollisionStart>,
+    mut collision_end_reader: MessageReader<CollisionEnd>,
+    mut param: EnemyAttackSystemParam,
+) {
+    for event in collision_start_reader.read() {
+        if let Some((enemy, target)) =
+            param.handle_collision(event.collider1, event.collider2, event.body1, event.body2)
+        {
+            if let Ok(mut attack) = param.enemy_q.get_mut(enemy) {
+                if !attack.targets.contains(&target) {
+                    attack.targets.push(target);
+                }
+            }
+        }
+    }
+
+    for event in collision_end_reader.read() {
+        if let Some((enemy, target)) =
+            param.handle_collision(event.collider1, event.collider2, event.body1, event.body2)
+        {
+            if let Ok(mut attack) = param.enemy_q.get_mut(enemy) {
+                attack.targets.retain(|item| *item != target);
+            }
+        }
+    }
+}
+
 //敌人攻击玩家
 fn on_enemy_attack(
-    mut collision_reader: MessageReader<CollisionStart>,
-    mut param: EnemyAttackSystemParam,
+    mut enemy_q: Query<(&mut EnemyAttack, &SkillAttributeSet, Entity)>,
+    health_q: Query<(), (With<Health>, Without<Enemy>)>,
+    mut writer: MessageWriter<DamageEvent>,
+    time: Res<Time>,
 ) {
-    let mut die_set = HashSet::new();
+    for (mut attack, attribute_set, entity) in enemy_q.iter_mut() {
+        //冷却受攻击速度影响
+        let attack_speed = attribute_set
+            .get_current_value(ATTACK_SPEED)
+            .unwrap_or_default();
+        attack
+            .timer
+            .tick(Duration::from_secs_f32(scale_by_attack_speed(
+                time.delta_secs(),
+                attack_speed,
+            )));
+
+        //目标可能已经被摧毁
+        attack.targets.retain(|target| health_q.contains(*target));
+
+        if !attack.timer.is_finished() {
+            continue;
+        }
+
+        let Some(target) = attack.targets.first().copied() else {
+            continue;
+        };
 
-    for event in collision_reader.read() {
-        if let Some((body1, body2)) = param.handle_collision_start(event) {
-            param.apply(body1, body2, &mut die_set);
+        tracing::debug!("enemy attack start");
+
+        writer.write(DamageEvent {
+            source: entity,
+            target,
+            amount: attribute_set
+                .get_current_value(ATTACK_DAMAGE)
+                .unwrap_or_default(),
+            kind: DamageKind::Physical,
+        });
+
+        attack.timer.reset();
+    }
+}
+
+//击杀敌人获得奖励
+fn grant_enemy_reward(
+    mut reader: MessageReader<Died>,
+    enemy_q: Query<&Enemy>,
+    mut writer: MessageWriter<ProductMeta>,
+) {
+    for event in reader.read() {
+        let Ok(enemy) = enemy_q.get(event.entity) else {
+            continue;
+        };
+
+        if enemy.reward > 0 {
+            writer.write(ProductMeta {
+                name: "sunlight".to_string(),
+                value: enemy.reward as f32,
+            });
         }
     }
+}
+
+///敌人的攻击，接触到目标后按照冷却进行攻击
+#[derive(Debug, Component)]
+pub struct EnemyAttack {
+    pub timer: Timer,
+    pub targets: Vec<Entity>,
+}
 
-    for entity in die_set.iter() {
-        param.commands.entity(*entity).despawn();
+impl EnemyAttack {
+    pub fn from_data(data: &EnemyData) -> Self {
+        let mut timer = Timer::from_seconds(data.attack_cooldown, TimerMode::Once);
+        //第一次接触时立即攻击
+        timer.finish();
+
+        EnemyAttack {
+            timer,
+            targets: vec![],
+        }
     }
 }
 
@@ -94,17 +317,18 @@ impl EnemySpawnerContainer {
         self.0.insert(name.to_string(), Box::new(spawner));
     }
 
+    ///没有注册生成器的敌人使用默认的生成器
     pub fn spawn_enemy(
         &self,
         commands: &mut EntityCommands,
         asset_server: &AssetServer,
         position: Vec3,
-        enemy: &str,
-    ) {
-        if let Some(spawner) = self.0.get(enemy) {
-            spawner.spawn_enemy(commands, asset_server, position);
+        data: &EnemyData,
+    ) -> Entity {
+        if let Some(spawner) = self.0.get(&data.item_name) {
+            spawner.spawn_enemy(commands, asset_server, position, data)
         } else {
-            tracing::error!("{} enemy spawner not match.", enemy);
+            DefaultEnemySpawner.spawn_enemy(commands, asset_server, position, data)
         }
     }
 }
@@ -115,33 +339,35 @@ pub trait EnemySpawner: 'static + Debug + Send + Sync {
         commands: &mut EntityCommands,
         asset_server: &AssetServer,
         position: Vec3,
-    );
+        data: &EnemyData,
+    ) -> Entity;
 }
 
 #[derive(Debug)]
-pub struct SquareEnemySpawner;
+pub struct DefaultEnemySpawner;
 
-impl EnemySpawner for SquareEnemySpawner {
+impl EnemySpawner for DefaultEnemySpawner {
     fn spawn_enemy(
         &self,
         commands: &mut EntityCommands,
         asset_server: &AssetServer,
         position: Vec3,
-    ) {
-        let image = asset_server.load("images/enemy/square.png");
+        data: &EnemyData,
+    ) -> Entity {
+        let image = data.get_enemy_image(asset_server);
 
-        let collider = Collider::rectangle(80.0, 80.0);
+        let collider = Collider::rectangle(data.size, data.size);
 
         let parent = commands.id();
 
         let mut commands = commands.commands();
 
         let mut entity_commands = commands.spawn((
-            Enemy,
-            Square,
+            Enemy::from_data(data),
+            EnemyAttack::from_data(data),
             Sprite {
                 image,
-                custom_size: Some(Vec2::splat(80.0)),
+                custom_size: Some(Vec2::splat(data.size)),
                 ..default()
             },
             RigidBody::Kinematic,
@@ -152,8 +378,10 @@ impl EnemySpawner for SquareEnemySpawner {
                 translation: position,
                 ..default()
             },
-            Stas::default(),
-            Name::new("Square"),
+            Health::new(data.health),
+            data.get_combat_stats().to_attribute_set(),
+            data.light_source_policy,
+            Name::new(data.item_name.clone()),
             SleepingDisabled,
             NavigatorPath::default(),
         ));
@@ -173,101 +401,250 @@ impl EnemySpawner for SquareEnemySpawner {
         );
 
         commands.entity(parent).add_child(enemy);
+
+        enemy
+    }
+}
+
+#[derive(Debug)]
+pub struct SquareEnemySpawner;
+
+impl EnemySpawner for SquareEnemySpawner {
+    fn spawn_enemy(
+        &self,
+        commands: &mut EntityCommands,
+        asset_server: &AssetServer,
+        position: Vec3,
+        data: &EnemyData,
+    ) -> Entity {
+        let enemy = DefaultEnemySpawner.spawn_enemy(commands, asset_server, position, data);
+
+        commands.commands().entity(enemy).insert(Square);
+
+        enemy
     }
 }
 
 #[derive(Debug, Component)]
-pub struct Enemy;
+pub struct Enemy {
+    //被击杀后奖励的阳光
+    pub reward: u32,
+}
+
+impl Enemy {
+    pub fn from_data(data: &EnemyData) -> Self {
+        Enemy {
+            reward: data.reward,
+        }
+    }
+}
 
 #[derive(Debug, Component)]
 pub struct Square;
 
+///敌人强度的倍率，由巢穴设置
 #[derive(Debug, Component)]
-pub struct LightSourcePosition(Vec3);
+pub struct EnemyStrength(pub f32);
 
-//寻找光源
-fn find_light_source(
+//按照强度调整最大生命值和攻击伤害的基础值
+fn apply_enemy_strength(
+    mut enemy_q: Query<(&EnemyStrength, &mut Health, &mut SkillAttributeSet), Added<EnemyStrength>>,
+) {
+    for (strength, mut health, mut attribute_set) in enemy_q.iter_mut() {
+        for attribute_name in [MAX_HEALTH, ATTACK_DAMAGE] {
+            let Some(attribute) = attribute_set.skill_attribute(attribute_name) else {
+                continue;
+            };
+
+            let value = ((attribute.base_value() as f32 * strength.0).round()).max(1.0);
+            attribute_set.set_attribute_base_value(attribute_name, value);
+        }
+
+        health.value = ((health.value as f32 * strength.0).round() as i32).max(1);
+    }
+}
+
+//目标光源变化、导航网格重建或偏离路径时，重新请求路径
+fn request_navigator_path(
     mut commands: Commands,
-    mut enemy_query: Query<(Ref<GlobalTransform>, Entity), With<Enemy>>,
-    light_source_q: Query<Ref<GlobalTransform>, With<LightSource>>,
+    mut navmesh_events: MessageReader<AssetEvent<NavMesh>>,
+    changed_query: Query<Entity, (With<Enemy>, Changed<LightSourcePosition>)>,
+    path_query: Query<
+        (Entity, &GlobalTransform, &NavigatorPath),
+        (
+            With<Enemy>,
+            With<LightSourcePosition>,
+            Without<NavigatorPathRequest>,
+        ),
+    >,
 ) {
-    let light_sources: Vec<GlobalTransform> =
-        light_source_q.iter().map(|item| item.clone()).collect();
+    let navmesh_rebuilt = navmesh_events.read().any(|event| {
+        matches!(
+            event,
+            AssetEvent::Added { .. } | AssetEvent::Modified { .. }
+        )
+    });
+
+    for entity in changed_query.iter() {
+        commands.entity(entity).insert(NavigatorPathRequest);
+    }
 
-    for (_transform, entity) in enemy_query.iter_mut() {
-        if light_source_q.is_empty() {
-            commands.entity(entity).remove::<LightSourcePosition>();
-        } else {
-            //根据策略设定光源位置 todo
-            commands.entity(entity).insert(LightSourcePosition(
-                light_sources.first().unwrap().translation(),
-            ));
+    for (entity, transform, navigator_path) in path_query.iter() {
+        if navmesh_rebuilt || navigator_path.is_drifted(transform.translation()) {
+            commands.entity(entity).insert(NavigatorPathRequest);
         }
     }
 }
 
 //添加导航
 fn find_navigator_path(
-    mut query: Query<(
-        Ref<GlobalTransform>,
-        &mut NavigatorPath,
-        &LightSourcePosition,
-    )>,
+    mut commands: Commands,
+    mut query: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &mut NavigatorPath,
+            &LightSourcePosition,
+        ),
+        With<NavigatorPathRequest>,
+    >,
     navmeshes: Res<Assets<NavMesh>>,
     navmesh: Single<&ManagedNavMesh>,
+    budget: Res<NavigatorPathBudget>,
 ) {
     let Some(navmesh) = navmeshes.get(*navmesh) else {
         return;
     };
 
-    for (transform, mut navigator_path, light_source_position) in query.iter_mut() {
-        let Some(path) = navmesh.transformed_path(transform.translation(), light_source_position.0)
+    let max_per_frame = budget.max_per_frame.unwrap_or(usize::MAX);
+
+    for (entity, transform, mut navigator_path, light_source_position) in
+        query.iter_mut().take(max_per_frame)
+    {
+        commands.entity(entity).remove::<NavigatorPathRequest>();
+
+        let Some(path) =
+            navmesh.transformed_path(transform.translation(), light_source_position.position)
         else {
             continue;
         };
 
-        if let Some((first, remaining)) = path.path.split_first() {
-            let mut remaining = remaining.to_vec();
-            remaining.reverse();
-
-            navigator_path.current = *first;
-            navigator_path.next = remaining.to_vec();
-        }
+        navigator_path.set_path(transform.translation(), &path.path);
     }
 }
 
 pub fn move_enemy(
-    mut navigator: Query<(
-        &GlobalTransform,
-        &NavigatorPath,
-        Entity,
-        &mut LinearVelocity,
-        &Enemy,
-        Option<&LightSourcePosition>,
-    )>,
+    mut navigator: Query<
+        (
+            &GlobalTransform,
+            &mut NavigatorPath,
+            Entity,
+            &mut LinearVelocity,
+            &SkillAttributeSet,
+            Option<&LightSourcePosition>,
+        ),
+        With<Enemy>,
+    >,
+    steering: Res<NavigatorSteering>,
+    navigator_mode: Res<NavigatorMode>,
+    flow_fields: Res<FlowFields>,
+    time: Res<Time>,
 ) {
-    for (transform, path, _entity, mut linvel, _enemy, light_source_position) in
+    let grid = SeparationGrid::new(
+        steering.separation_radius,
+        navigator
+            .iter()
+            .map(|(transform, ..)| transform.translation().truncate()),
+    );
+
+    for (transform, mut path, _entity, mut linvel, attribute_set, light_source_position) in
         navigator.iter_mut()
     {
-        if light_source_position.is_none() {
+        let Some(light_source_position) = light_source_position else {
             linvel.0 = Vec2::ZERO;
-        } else {
-            let move_direction = path.current - transform.translation();
-            linvel.0 = move_direction.truncate().normalize() * 50.0;
+            continue;
+        };
 
-            if transform.translation().distance(path.current) < 50.0 && path.next.is_empty() {
-                linvel.0 = Vec2::ZERO;
-                continue;
+        let position = transform.translation();
+
+        let target_position = match *navigator_mode {
+            NavigatorMode::NavMesh => {
+                //到达路径点后前往下一个
+                while position.distance(path.current) < steering.arrival_radius {
+                    if !path.advance() {
+                        break;
+                    }
+                }
+
+                if path.next.is_empty() && position.distance(path.current) < steering.arrival_radius
+                {
+                    None
+                } else {
+                    Some(path.current)
+                }
             }
-        }
+            NavigatorMode::FlowField => flow_fields
+                .next(light_source_position.entity, position)
+                .and_then(|next| {
+                    let next_position = get_item_position(next.x, next.y).extend(position.z);
+
+                    //已经到达光源所在的格子
+                    if next == get_position_i(position.truncate())
+                        && position.distance(next_position) < steering.arrival_radius
+                    {
+                        None
+                    } else {
+                        Some(next_position)
+                    }
+                }),
+        };
+
+        let Some(target_position) = target_position else {
+            linvel.0 = Vec2::ZERO;
+            continue;
+        };
+
+        let direction = (target_position - position).truncate().normalize_or_zero();
+        let separation =
+            steering.separation(position.truncate(), grid.neighbors(position.truncate()));
+        //速度受减速和眩晕等状态影响
+        let speed = attribute_set
+            .skill_attribute(MOVE_SPEED)
+            .map(|attribute| attribute.get_current_value().max(0) as f32)
+            .unwrap_or_default();
+        let target =
+            (direction + separation * steering.separation_weight).normalize_or_zero() * speed;
+
+        linvel.0 = steering.steer(linvel.0, target, time.delta_secs());
     }
 }
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(
         PreUpdate,
-        (find_light_source, find_navigator_path, move_enemy).chain(),
+        (
+            find_light_source,
+            (request_navigator_path, find_navigator_path)
+                .chain()
+                .run_if(resource_equals(NavigatorMode::NavMesh)),
+            move_enemy,
+        )
+            .chain(),
+    );
+
+    app.init_asset_loader::<EnemyDataLoader>();
+    app.init_asset::<EnemyData>();
+    app.load_resource::<EnemyDataAssets>();
+
+    app.add_systems(
+        Update,
+        (
+            apply_enemy_strength,
+            (update_enemy_attack_targets, on_enemy_attack)
+                .chain()
+                .before(DamageSystems::Apply),
+        ),
     );
 
-    app.add_systems(Update, on_enemy_attack);
+    app.add_systems(Update, grant_enemy_reward.in_set(DamageSystems::Died));
 }
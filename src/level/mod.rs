@@ -1,12 +1,19 @@
+mod outcome;
 mod unit_data_collection;
+mod unit_panel;
 
+pub use outcome::*;
 pub use unit_data_collection::*;
+pub use unit_panel::*;
 
 use bevy::prelude::*;
 
 use crate::{
     common::{Sunlight, SunlightProductProcessor},
-    map::{MapData, spawn_map},
+    map::{
+        ENVIRONMENT_DARKNESS, MapData, MapEnvironment, MapEnvironmentGoalComparison,
+        MapEnvironmentGoalData, MapSystemParams, WaveState, spawn_map,
+    },
     navigator::spawn_nav_mesh,
     player::Player,
     product::{ProductMeta, ProductSystem},
@@ -17,11 +24,26 @@ use crate::{
 #[derive(Debug, Component)]
 pub struct SunlightText;
 
+#[derive(Debug, Component)]
+pub struct WaveText;
+
+#[derive(Debug, Component)]
+pub struct EnvironmentText;
+
 #[derive(Debug, Default)]
 pub struct LevelCollection {
     sunlight: u32,
 }
 
+///关卡统计
+#[derive(Debug, Default, Clone)]
+pub struct LevelStats {
+    //关卡进行的时间，单位为秒
+    pub elapsed: f32,
+    pub sunlight_collected: u32,
+    pub units_placed: u32,
+}
+
 pub fn update_level_collection_panel(
     level: Res<Level>,
     sunlight: Single<&mut Text, With<SunlightText>>,
@@ -36,6 +58,60 @@ pub fn update_level_collection_panel(
     sunlight.0 = format!("{}", level.collection.sunlight);
 }
 
+pub fn update_wave_text(wave_state: Res<WaveState>, wave_text: Single<&mut Text, With<WaveText>>) {
+    let mut wave_text = wave_text.into_inner();
+
+    if wave_state.wave_count == 0 {
+        wave_text.0 = String::new();
+        return;
+    }
+
+    let current_wave = (wave_state.current_wave + 1).min(wave_state.wave_count);
+
+    wave_text.0 = if let Some(time_to_next_wave) = wave_state.time_to_next_wave {
+        format!(
+            "Wave: {}/{}  Next: {:.0}s",
+            current_wave, wave_state.wave_count, time_to_next_wave
+        )
+    } else {
+        format!("Wave: {}/{}", current_wave, wave_state.wave_count)
+    };
+}
+
+pub fn update_environment_text(
+    level: Res<Level>,
+    environment: Single<&MapEnvironment>,
+    environment_text: Single<&mut Text, With<EnvironmentText>>,
+) {
+    let mut environment_text = environment_text.into_inner();
+
+    let mut text = format!(
+        "Darkness: {}  Spawn: {:.0}%  Strength: {:.0}%",
+        environment.get_property(ENVIRONMENT_DARKNESS),
+        environment.spawn_rate() * 100.0,
+        environment.enemy_strength() * 100.0,
+    );
+
+    if let Some(goal) = level.environment_goal() {
+        let comparison = match goal.comparison {
+            MapEnvironmentGoalComparison::Below => "<=",
+            MapEnvironmentGoalComparison::Above => ">=",
+        };
+
+        text.push_str(&format!(
+            "  Goal: {} {} {} ({})",
+            goal.name,
+            comparison,
+            goal.threshold,
+            environment.get_property(&goal.name)
+        ));
+    }
+
+    if environment_text.0 != text {
+        environment_text.0 = text;
+    }
+}
+
 pub fn spawn_level_collection_panel(commands: &mut Commands, level_collection: &LevelCollection) {
     commands.spawn((
         Node {
@@ -80,7 +156,37 @@ pub fn spawn_level_collection_panel(commands: &mut Commands, level_collection: &
                         ..default()
                     },
                 ],
-            )
+            ),
+            (
+                Node {
+                    margin: UiRect::left(px(32)),
+                    ..default()
+                },
+                children![(
+                    Text::new(""),
+                    TextFont {
+                        font_size: 32.0,
+                        ..default()
+                    },
+                    WaveText,
+                    Name::new("WaveText"),
+                )],
+            ),
+            (
+                Node {
+                    margin: UiRect::left(px(32)),
+                    ..default()
+                },
+                children![(
+                    Text::new(""),
+                    TextFont {
+                        font_size: 32.0,
+                        ..default()
+                    },
+                    EnvironmentText,
+                    Name::new("EnvironmentText"),
+                )],
+            ),
         ],
     ));
 }
@@ -89,9 +195,80 @@ pub fn spawn_level_collection_panel(commands: &mut Commands, level_collection: &
 pub struct Level {
     product_system: ProductSystem,
     collection: LevelCollection,
+    //当前关卡使用的地图
+    map_name: String,
+    next_map: Option<String>,
+    survival_time: Option<f32>,
+    environment_goal: Option<MapEnvironmentGoalData>,
+    stats: LevelStats,
+    outcome: Option<LevelOutcome>,
 }
 
 impl Level {
+    ///重置关卡数据，在进入关卡时调用
+    pub fn start(&mut self, map_data: &MapData) {
+        self.collection = LevelCollection::default();
+        self.stats = LevelStats::default();
+        self.outcome = None;
+        self.next_map = map_data.next_map.clone();
+        self.survival_time = map_data.survival_time;
+        self.environment_goal = map_data.environment_goal.clone();
+    }
+
+    ///切换到下一关，没有下一关时返回false
+    pub fn advance(&mut self) -> bool {
+        if let Some(next_map) = self.next_map.take() {
+            self.map_name = next_map;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn has_next_map(&self) -> bool {
+        self.next_map.is_some()
+    }
+
+    pub fn environment_goal(&self) -> Option<&MapEnvironmentGoalData> {
+        self.environment_goal.as_ref()
+    }
+
+    pub fn stats(&self) -> &LevelStats {
+        &self.stats
+    }
+
+    pub fn outcome(&self) -> Option<LevelOutcome> {
+        self.outcome
+    }
+
+    pub fn record_unit_placed(&mut self) {
+        self.stats.units_placed += 1;
+    }
+
+    pub fn sunlight(&self) -> u32 {
+        self.collection.sunlight
+    }
+
+    pub fn can_afford(&self, cost: u32) -> bool {
+        self.collection.sunlight >= cost
+    }
+
+    ///扣除阳光，阳光不足时返回false
+    pub fn spend_sunlight(&mut self, cost: u32) -> bool {
+        if !self.can_afford(cost) {
+            return false;
+        }
+
+        self.collection.sunlight -= cost;
+
+        true
+    }
+
+    ///返还阳光，例如出售单位，不计入收集的阳光
+    pub fn refund_sunlight(&mut self, value: u32) {
+        self.collection.sunlight += value;
+    }
+
     pub fn collect_product(&mut self, reader: &mut MessageReader<ProductMeta>) {
         let mut sunlight = 0;
 
@@ -111,6 +288,7 @@ impl Level {
         }
 
         self.collection.sunlight += sunlight;
+        self.stats.sunlight_collected += sunlight;
     }
 }
 
@@ -122,6 +300,12 @@ impl Default for Level {
         Level {
             product_system,
             collection: LevelCollection::default(),
+            map_name: "level1".to_string(),
+            next_map: None,
+            survival_time: None,
+            environment_goal: None,
+            stats: LevelStats::default(),
+            outcome: None,
         }
     }
 }
@@ -133,28 +317,49 @@ fn collect_product(mut level: ResMut<Level>, mut reader: MessageReader<ProductMe
 pub(super) fn plugin(app: &mut App) {
     app.init_resource::<Level>();
 
-    app.add_plugins(unit_data_collection::plugin);
+    app.add_plugins((
+        unit_data_collection::plugin,
+        outcome::plugin,
+        unit_panel::plugin,
+    ));
     app.add_systems(
         Update,
-        (collect_product, update_level_collection_panel).run_if(in_state(Screen::Gameplay)),
+        (
+            collect_product,
+            update_level_collection_panel,
+            update_wave_text,
+            update_environment_text,
+        )
+            .run_if(in_state(Screen::Gameplay)),
     );
 }
 
 pub fn spawn_level(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    map_data: Res<MapData>,
+    map_system_params: MapSystemParams,
     collection: Res<UnitDataCollection>,
-    level: Res<Level>,
+    mut level: ResMut<Level>,
     unit_system_params: UnitSystemParams,
 ) {
-    commands.spawn(Player);
+    commands.spawn((Player, DespawnOnExit(Screen::Gameplay)));
 
-    spawn_map(&mut commands, &asset_server, &map_data, &unit_system_params);
+    if let Some(map_data) = map_system_params.get_map_data(&level.map_name) {
+        level.start(&map_data);
 
-    spawn_nav_mesh(&mut commands);
+        spawn_map(&mut commands, &asset_server, &map_data, &unit_system_params);
+        spawn_nav_mesh(&mut commands, map_data.get_map_size());
+        commands.insert_resource(map_data.navigator_mode.for_map(map_data.x, map_data.y));
+    } else {
+        tracing::error!("{} map data not found.", level.map_name);
+    }
 
-    spawn_unit_data_collection_panel(&mut commands, &asset_server, &collection);
+    spawn_unit_data_collection_panel(
+        &mut commands,
+        &asset_server,
+        &collection,
+        &unit_system_params,
+    );
 
     spawn_level_collection_panel(&mut commands, &level.collection);
 }
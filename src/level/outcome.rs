@@ -0,0 +1,221 @@
+use bevy::prelude::*;
+
+use crate::{
+    Pause,
+    common::LightSource,
+    enemy::Enemy,
+    level::Level,
+    map::{Lair, LairFinished, MapEnvironment, MapEnvironmentGoalData},
+    menus::Menu,
+    screens::Screen,
+};
+
+///关卡结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelOutcome {
+    Victory,
+    Defeat,
+}
+
+///判断关卡结果需要的数据
+#[derive(Default)]
+struct OutcomeState<'a> {
+    has_light_source: bool,
+    //所有巢穴都已生成完毕
+    lairs_finished: bool,
+    has_enemy: bool,
+    //关卡进行的时间，单位为秒
+    elapsed: f32,
+    survival_time: Option<f32>,
+    environment_goal: Option<&'a MapEnvironmentGoalData>,
+    environment: Option<&'a MapEnvironment>,
+}
+
+fn check_outcome(state: &OutcomeState) -> Option<LevelOutcome> {
+    //所有光源被摧毁时失败
+    if !state.has_light_source {
+        return Some(LevelOutcome::Defeat);
+    }
+
+    //环境属性达到目标后胜利
+    if state
+        .environment_goal
+        .zip(state.environment)
+        .is_some_and(|(goal, environment)| goal.is_reached(environment))
+    {
+        return Some(LevelOutcome::Victory);
+    }
+
+    //坚持指定的时间后胜利
+    if state
+        .survival_time
+        .is_some_and(|survival_time| state.elapsed >= survival_time)
+    {
+        return Some(LevelOutcome::Victory);
+    }
+
+    //所有巢穴生成完毕并且敌人被清除后胜利
+    if state.lairs_finished && !state.has_enemy {
+        return Some(LevelOutcome::Victory);
+    }
+
+    None
+}
+
+fn tick_level_time(mut level: ResMut<Level>, time: Res<Time>) {
+    level.stats.elapsed += time.delta_secs();
+}
+
+//检查关卡是否结束
+fn check_level_outcome(
+    mut level: ResMut<Level>,
+    light_source_q: Query<(), With<LightSource>>,
+    lair_q: Query<Has<LairFinished>, With<Lair>>,
+    enemy_q: Query<(), With<Enemy>>,
+    environment_q: Query<&MapEnvironment>,
+    mut next_menu: ResMut<NextState<Menu>>,
+    mut next_pause: ResMut<NextState<Pause>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    let Some(outcome) = check_outcome(&OutcomeState {
+        has_light_source: !light_source_q.is_empty(),
+        lairs_finished: !lair_q.is_empty() && lair_q.iter().all(|finished| finished),
+        has_enemy: !enemy_q.is_empty(),
+        elapsed: level.stats.elapsed,
+        survival_time: level.survival_time,
+        environment_goal: level.environment_goal(),
+        environment: environment_q.iter().next(),
+    }) else {
+        return;
+    };
+
+    tracing::info!("Level finished: {:?}, stats: {:?}", outcome, level.stats);
+
+    level.outcome = Some(outcome);
+
+    time.pause();
+    next_pause.set(Pause(true));
+    next_menu.set(Menu::Results);
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn level_running(level: Res<Level>) -> bool {
+    level.outcome.is_none()
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (tick_level_time, check_level_outcome)
+            .chain()
+            .run_if(in_state(Screen::Gameplay).and(level_running)),
+    );
+
+    app.add_systems(OnExit(Screen::Gameplay), resume_time);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::map::{ENVIRONMENT_DARKNESS, MapEnvironmentData, MapEnvironmentGoalComparison};
+
+    use super::*;
+
+    fn running() -> OutcomeState<'static> {
+        OutcomeState {
+            has_light_source: true,
+            has_enemy: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check_outcome_light_source() {
+        assert_eq!(check_outcome(&running()), None);
+
+        //光源被摧毁时即使满足胜利条件也失败
+        let state = OutcomeState {
+            has_light_source: false,
+            lairs_finished: true,
+            has_enemy: false,
+            ..running()
+        };
+        assert_eq!(check_outcome(&state), Some(LevelOutcome::Defeat));
+    }
+
+    #[test]
+    fn test_check_outcome_survival_time() {
+        let state = OutcomeState {
+            elapsed: 59.0,
+            survival_time: Some(60.0),
+            ..running()
+        };
+        assert_eq!(check_outcome(&state), None);
+
+        let state = OutcomeState {
+            elapsed: 60.0,
+            ..state
+        };
+        assert_eq!(check_outcome(&state), Some(LevelOutcome::Victory));
+    }
+
+    #[test]
+    fn test_check_outcome_lairs_finished() {
+        let state = OutcomeState {
+            lairs_finished: true,
+            ..running()
+        };
+        assert_eq!(check_outcome(&state), None);
+
+        let state = OutcomeState {
+            has_enemy: false,
+            ..state
+        };
+        assert_eq!(check_outcome(&state), Some(LevelOutcome::Victory));
+    }
+
+    #[test]
+    fn test_check_outcome_environment_goal() {
+        let environment = MapEnvironment::from_data(
+            &[MapEnvironmentData {
+                name: ENVIRONMENT_DARKNESS.to_string(),
+                value: 20.0,
+            }],
+            &[],
+        );
+
+        let goal = |comparison, threshold| MapEnvironmentGoalData {
+            name: ENVIRONMENT_DARKNESS.to_string(),
+            comparison,
+            threshold,
+        };
+
+        let check = |goal: &MapEnvironmentGoalData| {
+            check_outcome(&OutcomeState {
+                environment_goal: Some(goal),
+                environment: Some(&environment),
+                ..running()
+            })
+        };
+
+        assert_eq!(
+            check(&goal(MapEnvironmentGoalComparison::Below, 20)),
+            Some(LevelOutcome::Victory)
+        );
+        assert_eq!(check(&goal(MapEnvironmentGoalComparison::Below, 10)), None);
+        assert_eq!(
+            check(&goal(MapEnvironmentGoalComparison::Above, 20)),
+            Some(LevelOutcome::Victory)
+        );
+        assert_eq!(check(&goal(MapEnvironmentGoalComparison::Above, 30)), None);
+
+        //没有环境时不会达到目标
+        let state = OutcomeState {
+            environment_goal: Some(&goal(MapEnvironmentGoalComparison::Below, 20)),
+            ..running()
+        };
+        assert_eq!(check_outcome(&state), None);
+    }
+}
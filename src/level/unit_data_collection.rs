@@ -4,11 +4,15 @@ use bevy::{ecs::relationship::RelatedSpawnerCommands, prelude::*};
 
 use crate::{
     common::ProgressBar,
+    level::Level,
     map::{MapItemData, MapState},
     screens::Screen,
-    unit::UnitData,
+    unit::{UnitData, UnitSystemParams},
 };
 
+///阳光不足时按钮图片的颜色
+const UNAFFORDABLE_COLOR: Color = Color::srgb(0.35, 0.35, 0.35);
+
 #[derive(Debug, Component)]
 pub struct UsedCooldownTimer(Timer);
 
@@ -18,6 +22,32 @@ pub struct UsedCooldownTimerNode(Entity);
 #[derive(Debug, Component)]
 pub struct UsedCooldownTimerText(Entity);
 
+#[derive(Debug, Component)]
+pub struct UnitDataButtonImage(Entity);
+
+//阳光不足时置灰
+fn update_unit_data_button_image_system(
+    mut image_q: Query<(&mut ImageNode, &UnitDataButtonImage)>,
+    button_q: Query<&UnitDataButton>,
+    level: Res<Level>,
+) {
+    for (mut image, target) in image_q.iter_mut() {
+        let Ok(button) = button_q.get(target.0) else {
+            continue;
+        };
+
+        let color = if level.can_afford(button.unit_data.cost) {
+            Color::WHITE
+        } else {
+            UNAFFORDABLE_COLOR
+        };
+
+        if image.color != color {
+            image.color = color;
+        }
+    }
+}
+
 fn update_used_cooldown_timer_text_system(
     mut text_q: Query<(&mut Text, &UsedCooldownTimerText)>,
     used_cooldown_timer_q: Query<&UsedCooldownTimer>,
@@ -93,10 +123,21 @@ fn unit_data_button_system(
     mut commands: Commands,
     mut button_q: Query<(&mut UnitDataButton, &Interaction, Entity), Changed<Interaction>>,
     mut map_data: ResMut<MapState>,
+    level: Res<Level>,
 ) {
     for (mut button, interaction, entity) in button_q.iter_mut() {
         match *interaction {
             Interaction::Pressed => {
+                if !level.can_afford(button.unit_data.cost) {
+                    tracing::debug!(
+                        "Not enough sunlight for {}: {} < {}",
+                        button.unit_data.item_name,
+                        level.sunlight(),
+                        button.unit_data.cost
+                    );
+                    continue;
+                }
+
                 if !button.disabled {
                     button.disabled = true;
 
@@ -170,6 +211,7 @@ fn unit_data_button(
                         ..default()
                     },
                     image,
+                    UnitDataButtonImage(entity),
                 ))
                 .observe(on_unit_data_button_out)
                 .observe(on_unit_data_button_over);
@@ -180,6 +222,7 @@ pub fn spawn_unit_data_collection_panel(
     commands: &mut Commands,
     asset_server: &AssetServer,
     collection: &UnitDataCollection,
+    unit_system_params: &UnitSystemParams,
 ) {
     commands
         .spawn((
@@ -204,27 +247,30 @@ pub fn spawn_unit_data_collection_panel(
                     ..default()
                 },))
                 .with_children(|parent| {
-                    for data in collection.items.iter() {
-                        unit_data_button(parent, asset_server, data);
+                    for item_name in collection.items.iter() {
+                        if let Some(data) = unit_system_params.get_unit_data(item_name) {
+                            unit_data_button(parent, asset_server, &data);
+                        } else {
+                            tracing::error!("{} unit data not found.", item_name);
+                        }
                     }
                 });
         });
 }
 
+///可放置的单位，数据由`UnitDataAssets`提供
 #[derive(Debug, Resource)]
 pub struct UnitDataCollection {
-    items: Vec<UnitData>,
+    items: Vec<String>,
 }
 
 impl Default for UnitDataCollection {
     fn default() -> Self {
         let mut items = vec![];
 
-        items.push(UnitData {
-            item_name: "arrow_tower".to_string(),
-            image: "TemporaryArrowTower".to_string(),
-            cooldown_timer: 1,
-        });
+        items.push("arrow_tower".to_string());
+        items.push("tesla_tower".to_string());
+        items.push("lighthouse".to_string());
 
         UnitDataCollection { items }
     }
@@ -241,6 +287,7 @@ pub(super) fn plugin(app: &mut App) {
             update_used_cooldown_timer_text_system,
             update_used_cooldown_timer_node_system,
             update_progress_bar_system,
+            update_unit_data_button_image_system,
         ),
     );
 }
@@ -0,0 +1,244 @@
+use bevy::prelude::*;
+
+use crate::{
+    common::TargetingStrategy,
+    level::Level,
+    map::{Map, MapPosition, MapState},
+    screens::Screen,
+    theme::widget,
+    unit::{Unit, UnitUpgradeSystemParams},
+};
+
+///当前选中的单位
+#[derive(Debug, Resource, Default)]
+pub struct SelectedUnit(pub Option<Entity>);
+
+///选中单位后显示的面板
+#[derive(Debug, Component)]
+pub struct UnitPanel;
+
+#[derive(Debug, Component)]
+pub struct UnitPanelText;
+
+//点击已放置的单位时选中
+fn select_unit(
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    map: Single<&Map>,
+    map_position: Single<&MapPosition>,
+    map_state: Res<MapState>,
+    interaction_q: Query<&Interaction>,
+    unit_q: Query<(), With<Unit>>,
+    mut selected_unit: ResMut<SelectedUnit>,
+) {
+    if !mouse_button_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    //正在放置单位
+    if map_state.selelcted_map_item_data.is_some() {
+        return;
+    }
+
+    //点击的是界面
+    if interaction_q
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+
+    let selected = map
+        .get_occupant(map_position.position_i())
+        .filter(|entity| unit_q.contains(*entity));
+
+    if selected_unit.0 != selected {
+        selected_unit.0 = selected;
+    }
+}
+
+//选中的单位被摧毁时取消选中
+fn check_selected_unit(mut selected_unit: ResMut<SelectedUnit>, unit_q: Query<(), With<Unit>>) {
+    if let Some(entity) = selected_unit.0 {
+        if !unit_q.contains(entity) {
+            selected_unit.0 = None;
+        }
+    }
+}
+
+fn update_unit_panel(
+    mut commands: Commands,
+    selected_unit: Res<SelectedUnit>,
+    panel_q: Query<Entity, With<UnitPanel>>,
+    targeting_q: Query<(), With<TargetingStrategy>>,
+) {
+    if !selected_unit.is_changed() {
+        return;
+    }
+
+    for entity in panel_q.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let Some(entity) = selected_unit.0 else {
+        return;
+    };
+
+    let mut panel = commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            right: px(16),
+            bottom: px(16),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: px(8),
+            padding: UiRect::all(px(8)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        Interaction::default(),
+        UnitPanel,
+        Name::new("UnitPanel"),
+        ZIndex(10),
+        DespawnOnExit(Screen::Gameplay),
+        children![
+            (widget::label(""), UnitPanelText),
+            widget::button("Upgrade", upgrade_selected_unit),
+            widget::button("Sell", sell_selected_unit),
+            widget::button("Close", close_unit_panel),
+        ],
+    ));
+
+    //攻击单位可以切换目标策略
+    if targeting_q.contains(entity) {
+        panel.with_child(widget::button("Target", switch_targeting_strategy));
+    }
+}
+
+fn update_unit_panel_text(
+    selected_unit: Res<SelectedUnit>,
+    upgrade_params: UnitUpgradeSystemParams,
+    targeting_q: Query<&TargetingStrategy>,
+    text: Single<&mut Text, With<UnitPanelText>>,
+) {
+    let Some(entity) = selected_unit.0 else {
+        return;
+    };
+
+    let Some(unit) = upgrade_params.get_unit(entity) else {
+        return;
+    };
+
+    let mut text = text.into_inner();
+
+    let upgrade = if let Some(upgrade) = upgrade_params.next_upgrade(entity) {
+        format!("Upgrade: {}", upgrade.cost)
+    } else {
+        "Max level".to_string()
+    };
+
+    let refund = upgrade_params.get_refund(entity).unwrap_or_default();
+
+    let mut value = format!(
+        "{} Lv.{}\n{}\nSell: {}",
+        unit.item_name(),
+        unit.level() + 1,
+        upgrade,
+        refund
+    );
+
+    if let Ok(strategy) = targeting_q.get(entity) {
+        value.push_str(&format!("\nTarget: {}", strategy.label()));
+    }
+
+    if text.0 != value {
+        text.0 = value;
+    }
+}
+
+fn upgrade_selected_unit(
+    _: On<Pointer<Click>>,
+    selected_unit: Res<SelectedUnit>,
+    mut level: ResMut<Level>,
+    mut upgrade_params: UnitUpgradeSystemParams,
+) {
+    let Some(entity) = selected_unit.0 else {
+        return;
+    };
+
+    let Some(upgrade) = upgrade_params.next_upgrade(entity) else {
+        return;
+    };
+
+    if !level.spend_sunlight(upgrade.cost) {
+        tracing::info!("Not enough sunlight to upgrade: {}.", upgrade.cost);
+        return;
+    }
+
+    upgrade_params.apply_upgrade(entity, &upgrade);
+}
+
+//出售单位，删除单位及其子实体，格子由地图在单位删除后释放
+fn sell_selected_unit(
+    _: On<Pointer<Click>>,
+    mut commands: Commands,
+    mut selected_unit: ResMut<SelectedUnit>,
+    mut level: ResMut<Level>,
+    upgrade_params: UnitUpgradeSystemParams,
+) {
+    let Some(entity) = selected_unit.0 else {
+        return;
+    };
+
+    let Some(refund) = upgrade_params.get_refund(entity) else {
+        return;
+    };
+
+    level.refund_sunlight(refund);
+
+    //作为障碍物的单位删除后，导航网格会随之更新
+    commands.entity(entity).despawn();
+
+    selected_unit.0 = None;
+
+    tracing::info!("Unit sold, refund {}.", refund);
+}
+
+fn switch_targeting_strategy(
+    _: On<Pointer<Click>>,
+    selected_unit: Res<SelectedUnit>,
+    mut targeting_q: Query<&mut TargetingStrategy>,
+) {
+    let Some(entity) = selected_unit.0 else {
+        return;
+    };
+
+    if let Ok(mut strategy) = targeting_q.get_mut(entity) {
+        *strategy = strategy.next();
+    }
+}
+
+fn close_unit_panel(_: On<Pointer<Click>>, mut selected_unit: ResMut<SelectedUnit>) {
+    selected_unit.0 = None;
+}
+
+fn reset_selected_unit(mut selected_unit: ResMut<SelectedUnit>) {
+    selected_unit.0 = None;
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SelectedUnit>();
+
+    app.add_systems(
+        Update,
+        (
+            select_unit,
+            check_selected_unit,
+            update_unit_panel,
+            update_unit_panel_text,
+        )
+            .chain()
+            .run_if(in_state(Screen::Gameplay)),
+    );
+
+    app.add_systems(OnExit(Screen::Gameplay), reset_selected_unit);
+}
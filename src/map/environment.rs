@@ -1,24 +1,215 @@
 use bevy::prelude::*;
 
-use crate::skill::SkillAttributeSet;
+use crate::{
+    map::{MapEnvironmentData, MapEnvironmentModifierData},
+    skill::{
+        SkillAttribute, SkillAttributeModifier, SkillAttributeModifierOperation, SkillAttributeSet,
+    },
+};
+
+///黑暗程度，越黑暗巢穴生成越快，敌人越强
+pub const ENVIRONMENT_DARKNESS: &str = "darkness";
+///巢穴生成速率，百分比
+pub const ENVIRONMENT_SPAWN_RATE: &str = "spawn_rate";
+///敌人强度，百分比
+pub const ENVIRONMENT_ENEMY_STRENGTH: &str = "enemy_strength";
 
 ///环境
 #[derive(Component)]
-pub struct MapEnvironment(SkillAttributeSet);
+pub struct MapEnvironment {
+    attribute_set: SkillAttributeSet,
+    //按照时间排序的修改器
+    modifiers: Vec<MapEnvironmentModifierData>,
+    //已经生效的修改器数量
+    applied: usize,
+    elapsed: f32,
+}
 
 impl MapEnvironment {
+    pub fn from_data(
+        data: &[MapEnvironmentData],
+        modifiers: &[MapEnvironmentModifierData],
+    ) -> Self {
+        let mut environment = MapEnvironment::default();
+
+        for item in data.iter() {
+            environment.add_attribute(&item.name, item.value);
+        }
+
+        environment.modifiers = modifiers.to_vec();
+        environment
+            .modifiers
+            .sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        environment
+    }
+
+    fn add_attribute(&mut self, name: &str, value: f32) {
+        let mut attribute = SkillAttribute::default();
+        attribute.name = name.to_string();
+        attribute.update_base_value(value);
+
+        self.attribute_set.add_skill_attribute(attribute);
+    }
+
     pub fn get_property(&self, name: &str) -> i32 {
-        if let Some(attribute) = self.0.skill_attribute(name) {
+        if let Some(attribute) = self.attribute_set.skill_attribute(name) {
             attribute.get_current_value()
         } else {
             tracing::warn!("{} not match.", name);
             return 0;
         }
     }
+
+    pub fn add_modifier(&mut self, name: &str, modifier: &SkillAttributeModifier) {
+        if let Some(attribute) = self.attribute_set.skill_attribute_mut(name) {
+            attribute.add_modifier(modifier);
+        } else {
+            tracing::warn!("{} not match.", name);
+        }
+    }
+
+    pub fn remove_modifier_with_source(&mut self, source: &str) {
+        self.attribute_set.remove_modifier_with_source(source);
+    }
+
+    ///黑暗带来的倍率
+    fn darkness_scale(&self) -> f32 {
+        1.0 + self.get_property(ENVIRONMENT_DARKNESS) as f32 / 100.0
+    }
+
+    ///巢穴生成速率的倍率
+    pub fn spawn_rate(&self) -> f32 {
+        (self.get_property(ENVIRONMENT_SPAWN_RATE) as f32 / 100.0 * self.darkness_scale()).max(0.0)
+    }
+
+    ///敌人强度的倍率
+    pub fn enemy_strength(&self) -> f32 {
+        (self.get_property(ENVIRONMENT_ENEMY_STRENGTH) as f32 / 100.0 * self.darkness_scale())
+            .max(0.0)
+    }
+
+    ///推进时间，应用到达时间的修改器
+    pub fn tick(&mut self, delta: f32) {
+        self.elapsed += delta;
+
+        while let Some(data) = self.modifiers.get(self.applied) {
+            if data.time > self.elapsed {
+                break;
+            }
+
+            let mut modifier = SkillAttributeModifier::default();
+            modifier.value = data.value;
+            modifier.source = format!("environment_modifier_{}", self.applied);
+            if data.percentage {
+                modifier.operation = SkillAttributeModifierOperation::Percentage;
+            }
+
+            let name = data.name.clone();
+            self.add_modifier(&name, &modifier);
+
+            self.applied += 1;
+        }
+    }
 }
 
 impl Default for MapEnvironment {
     fn default() -> Self {
-        MapEnvironment(Default::default())
+        let mut environment = MapEnvironment {
+            attribute_set: Default::default(),
+            modifiers: vec![],
+            applied: 0,
+            elapsed: 0.0,
+        };
+
+        environment.add_attribute(ENVIRONMENT_DARKNESS, 0.0);
+        environment.add_attribute(ENVIRONMENT_SPAWN_RATE, 100.0);
+        environment.add_attribute(ENVIRONMENT_ENEMY_STRENGTH, 100.0);
+
+        environment
+    }
+}
+
+///单位存在期间对环境的修改，例如灯塔降低黑暗程度
+#[derive(Debug, Component)]
+pub struct EnvironmentModifiers(pub Vec<MapEnvironmentData>);
+
+impl EnvironmentModifiers {
+    fn get_source(entity: Entity) -> String {
+        format!("environment_modifiers_{}", entity)
+    }
+}
+
+pub fn apply_environment_modifiers(
+    modifiers_q: Query<(&EnvironmentModifiers, Entity), Added<EnvironmentModifiers>>,
+    mut removed: RemovedComponents<EnvironmentModifiers>,
+    mut environment_q: Query<&mut MapEnvironment>,
+) {
+    let removed = removed.read().collect::<Vec<_>>();
+
+    for mut environment in environment_q.iter_mut() {
+        for entity in removed.iter() {
+            environment.remove_modifier_with_source(&EnvironmentModifiers::get_source(*entity));
+        }
+
+        for (modifiers, entity) in modifiers_q.iter() {
+            for data in modifiers.0.iter() {
+                let mut modifier = SkillAttributeModifier::default();
+                modifier.value = data.value;
+                modifier.source = EnvironmentModifiers::get_source(entity);
+
+                environment.add_modifier(&data.name, &modifier);
+            }
+        }
+    }
+}
+
+pub fn update_map_environment(mut environment_q: Query<&mut MapEnvironment>, time: Res<Time>) {
+    for mut environment in environment_q.iter_mut() {
+        environment.tick(time.delta_secs());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ENVIRONMENT_DARKNESS, ENVIRONMENT_SPAWN_RATE, MapEnvironment};
+    use crate::map::{MapEnvironmentData, MapEnvironmentModifierData};
+
+    #[test]
+    fn test_map_environment() {
+        let data = vec![MapEnvironmentData {
+            name: ENVIRONMENT_SPAWN_RATE.to_string(),
+            value: 50.0,
+        }];
+
+        let modifiers = vec![
+            MapEnvironmentModifierData {
+                time: 20.0,
+                name: ENVIRONMENT_SPAWN_RATE.to_string(),
+                value: 1.0,
+                percentage: true,
+            },
+            MapEnvironmentModifierData {
+                time: 10.0,
+                name: ENVIRONMENT_DARKNESS.to_string(),
+                value: 50.0,
+                percentage: false,
+            },
+        ];
+
+        let mut environment = MapEnvironment::from_data(&data, &modifiers);
+
+        assert_eq!(environment.get_property(ENVIRONMENT_SPAWN_RATE), 50);
+        assert_eq!(environment.spawn_rate(), 0.5);
+        assert_eq!(environment.enemy_strength(), 1.0);
+
+        environment.tick(10.0);
+        assert_eq!(environment.get_property(ENVIRONMENT_DARKNESS), 50);
+        assert_eq!(environment.spawn_rate(), 0.75);
+        assert_eq!(environment.enemy_strength(), 1.5);
+
+        environment.tick(10.0);
+        assert_eq!(environment.get_property(ENVIRONMENT_SPAWN_RATE), 100);
+        assert_eq!(environment.spawn_rate(), 1.5);
     }
 }
@@ -3,7 +3,7 @@ use std::fmt::Debug;
 use bevy::{platform::collections::HashMap, prelude::*};
 
 use crate::{
-    map::spawn_hill_map_item,
+    map::{LairFactoryContainer, spawn_hill_map_item},
     unit::{UnitSystemParams, spawn_unit},
 };
 
@@ -17,6 +17,7 @@ impl MapItemFactoryContainer {
         let mut container = MapItemFactoryContainer::empty();
         container.register(HillMapItemFactory);
         container.register(UnitMapItemFactory);
+        container.register(LairMapItemFactory::default());
 
         container
     }
@@ -37,7 +38,7 @@ impl MapItemFactoryContainer {
         item_data: &MapItemData,
         position: Vec3,
         unit_system_params: &UnitSystemParams,
-    ) {
+    ) -> Option<Entity> {
         if let Some(factory) = self.get_map_item_factory(&item_data.name) {
             factory.spawn_map_item(
                 commands,
@@ -45,10 +46,22 @@ impl MapItemFactoryContainer {
                 &item_data,
                 position,
                 unit_system_params,
-            );
+            )
+        } else {
+            tracing::error!("{} map item factory not match.", item_data.name);
+            None
         }
     }
 
+    pub fn is_obstacle(
+        &self,
+        item_data: &MapItemData,
+        unit_system_params: &UnitSystemParams,
+    ) -> bool {
+        self.get_map_item_factory(&item_data.name)
+            .is_some_and(|factory| factory.is_obstacle(item_data, unit_system_params))
+    }
+
     pub fn empty() -> Self {
         MapItemFactoryContainer(Default::default())
     }
@@ -70,7 +83,16 @@ pub trait MapItemFactory: 'static + Send + Sync + Debug {
         item_data: &MapItemData,
         position: Vec3,
         unit_system_params: &UnitSystemParams,
-    );
+    ) -> Option<Entity>;
+
+    //是否阻挡敌人的路径
+    fn is_obstacle(
+        &self,
+        _item_data: &MapItemData,
+        _unit_system_params: &UnitSystemParams,
+    ) -> bool {
+        false
+    }
 }
 
 #[derive(Debug)]
@@ -88,8 +110,21 @@ impl MapItemFactory for HillMapItemFactory {
         item_data: &MapItemData,
         position: Vec3,
         _unit_system_params: &UnitSystemParams,
-    ) {
-        spawn_hill_map_item(commands, asset_server, item_data, position);
+    ) -> Option<Entity> {
+        Some(spawn_hill_map_item(
+            commands,
+            asset_server,
+            item_data,
+            position,
+        ))
+    }
+
+    fn is_obstacle(
+        &self,
+        _item_data: &MapItemData,
+        _unit_system_params: &UnitSystemParams,
+    ) -> bool {
+        true
     }
 }
 
@@ -108,17 +143,59 @@ impl MapItemFactory for UnitMapItemFactory {
         item_data: &MapItemData,
         position: Vec3,
         unit_system_params: &UnitSystemParams,
-    ) {
+    ) -> Option<Entity> {
         if let Some(unit_data) = unit_system_params.get_unit_data(&item_data.unit_item_name) {
             spawn_unit(
                 commands,
                 asset_server,
                 position,
                 &unit_data,
+                unit_system_params.get_unit_skill(&unit_data),
                 &unit_system_params.unit_factory_container,
-            );
+            )
         } else {
             tracing::error!("{} unit data not found.", item_data.unit_item_name);
+            None
         }
     }
+
+    fn is_obstacle(&self, item_data: &MapItemData, unit_system_params: &UnitSystemParams) -> bool {
+        unit_system_params
+            .get_unit_data(&item_data.unit_item_name)
+            .is_some_and(|unit_data| unit_data.blocks_path)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LairMapItemFactory {
+    lair_factory_container: LairFactoryContainer,
+}
+
+impl MapItemFactory for LairMapItemFactory {
+    fn map_item_name(&self) -> &'static str {
+        "lair"
+    }
+
+    fn spawn_map_item(
+        &self,
+        commands: &mut EntityCommands,
+        _asset_server: &AssetServer,
+        item_data: &MapItemData,
+        position: Vec3,
+        _unit_system_params: &UnitSystemParams,
+    ) -> Option<Entity> {
+        let lair = {
+            let mut commands = commands.commands();
+            self.lair_factory_container.spawn_lair(
+                &mut commands,
+                position,
+                &item_data.lair_item_name,
+                &item_data.waves,
+            )
+        }?;
+
+        commands.add_child(lair);
+
+        Some(lair)
+    }
 }
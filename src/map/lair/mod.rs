@@ -1,50 +1,160 @@
-use std::marker::PhantomData;
+mod wave;
 
-use bevy::prelude::*;
+pub use wave::*;
+
+use std::{fmt::Debug, marker::PhantomData, time::Duration};
+
+use bevy::{ecs::component::Mutable, platform::collections::HashMap, prelude::*};
 
 use crate::{
-    enemy::{EnemySpawnerContainer, SquareEnemySpawner},
+    enemy::{EnemyData, EnemySpawnerContainer, EnemyStrength, EnemySystemParams},
     map::MapEnvironment,
+    screens::Screen,
 };
 
 #[derive(Debug, Component)]
 #[require(Transform)]
 pub struct Lair;
 
-pub trait LairTrait: 'static + Send + Sync {
+///巢穴已生成所有敌人
+#[derive(Debug, Component)]
+pub struct LairFinished;
+
+///生成敌人的最短间隔，间隔为0时计时器每一帧触发无数次
+const MIN_SPAWN_INTERVAL: f32 = 0.01;
+
+//巢穴生成敌人的计时器，时长在推进时按照波次设置
+fn spawn_timer() -> Timer {
+    Timer::new(Duration::ZERO, TimerMode::Repeating)
+}
+
+pub trait LairTrait: Component<Mutability = Mutable> + Debug {
+    fn is_enabled(&self) -> bool;
+
+    fn set_enabled(&mut self, enabled: bool);
+
+    fn enemy_spawner_container(&self) -> &EnemySpawnerContainer;
+
+    ///生成敌人的计时器
+    fn timer(&self) -> &Timer;
+
+    fn timer_mut(&mut self) -> &mut Timer;
+
+    ///生成速率的倍率
+    fn spawn_rate(&self) -> f32 {
+        1.0
+    }
+
+    ///生成敌人强度的倍率
+    fn enemy_strength(&self) -> f32 {
+        1.0
+    }
+
+    ///距离下一波次开始的时间，正在生成敌人或者巢穴停止时为None
+    fn time_to_next_wave(&self, lair_waves: &LairWaves) -> Option<f32> {
+        if !self.is_enabled() || lair_waves.is_spawning() || lair_waves.is_finished() {
+            return None;
+        }
+
+        let delay = lair_waves.next_delay()?.max(MIN_SPAWN_INTERVAL);
+
+        Some((delay - self.timer().elapsed_secs()).max(0.0))
+    }
+
+    ///推进波次，返回需要生成的敌人，生成速率受到环境的影响
+    fn tick(
+        &mut self,
+        lair_waves: &mut LairWaves,
+        delta: Duration,
+        map_environment: &MapEnvironment,
+    ) -> Vec<String> {
+        let mut enemies = vec![];
+
+        let Some(delay) = lair_waves.next_delay() else {
+            self.set_enabled(false);
+            return enemies;
+        };
+
+        let delta = delta.mul_f32(self.spawn_rate() * map_environment.spawn_rate());
+        let timer = self.timer_mut();
+
+        timer.set_duration(Duration::from_secs_f32(delay.max(MIN_SPAWN_INTERVAL)));
+        timer.tick(delta);
+
+        //一帧内可能需要生成多个敌人
+        for _ in 0..timer.times_finished_this_tick() {
+            let current_wave = lair_waves.current_wave();
+
+            if let Some(enemy) = lair_waves.spawn_next() {
+                enemies.push(enemy);
+            }
+
+            //进入下一波次后重新等待波次的延迟
+            if lair_waves.current_wave() != current_wave {
+                timer.reset();
+                break;
+            }
+        }
+
+        //所有波次生成完毕后停止巢穴
+        if lair_waves.is_finished() {
+            self.set_enabled(false);
+        }
+
+        enemies
+    }
+
     fn spaw_enemy(
         &self,
-        _commands: &mut EntityCommands,
-        _asset_server: &AssetServer,
-        _position: Vec3,
-        _enemy: &str,
-        _map_environment: &MapEnvironment,
-    );
+        commands: &mut EntityCommands,
+        asset_server: &AssetServer,
+        position: Vec3,
+        enemy_data: &EnemyData,
+        map_environment: &MapEnvironment,
+    ) -> Entity {
+        let enemy = self.enemy_spawner_container().spawn_enemy(
+            commands,
+            asset_server,
+            position,
+            enemy_data,
+        );
+
+        //敌人强度受到环境的影响
+        let strength = self.enemy_strength() * map_environment.enemy_strength();
+
+        if strength != 1.0 {
+            commands
+                .commands()
+                .entity(enemy)
+                .insert(EnemyStrength(strength));
+        }
+
+        enemy
+    }
 }
 
 ///方块巢穴
 #[derive(Debug, Component)]
 pub struct SquareLarir {
-    timer: Timer,
     enemy_spawner_container: EnemySpawnerContainer,
     enabled: bool,
+    timer: Timer,
 }
 
 impl SquareLarir {
     pub fn new() -> Self {
-        let mut larir = Self::empty();
-        larir
-            .enemy_spawner_container
-            .register("square", SquareEnemySpawner);
-
-        larir
+        SquareLarir {
+            enemy_spawner_container: EnemySpawnerContainer::new(),
+            enabled: true,
+            timer: spawn_timer(),
+        }
     }
 
     pub fn empty() -> Self {
         SquareLarir {
-            timer: Timer::from_seconds(1.0, TimerMode::Repeating),
             enemy_spawner_container: EnemySpawnerContainer::empty(),
             enabled: true,
+            timer: spawn_timer(),
         }
     }
 }
@@ -56,44 +166,167 @@ impl Default for SquareLarir {
 }
 
 impl LairTrait for SquareLarir {
-    fn spaw_enemy(
-        &self,
-        commands: &mut EntityCommands,
-        asset_server: &AssetServer,
-        position: Vec3,
-        enemy: &str,
-        map_environment: &MapEnvironment,
-    ) {
-        let _v = map_environment.get_property("test");
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn enemy_spawner_container(&self) -> &EnemySpawnerContainer {
+        &self.enemy_spawner_container
+    }
+
+    fn timer(&self) -> &Timer {
+        &self.timer
+    }
+
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+///快速巢穴，生成速度快，敌人较弱
+#[derive(Debug, Component)]
+pub struct FastLair {
+    enemy_spawner_container: EnemySpawnerContainer,
+    enabled: bool,
+    timer: Timer,
+}
+
+impl Default for FastLair {
+    fn default() -> Self {
+        FastLair {
+            enemy_spawner_container: EnemySpawnerContainer::new(),
+            enabled: true,
+            timer: spawn_timer(),
+        }
+    }
+}
+
+impl LairTrait for FastLair {
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn enemy_spawner_container(&self) -> &EnemySpawnerContainer {
+        &self.enemy_spawner_container
+    }
+
+    fn timer(&self) -> &Timer {
+        &self.timer
+    }
+
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
 
-        self.enemy_spawner_container
-            .spawn_enemy(commands, asset_server, position, enemy);
+    fn spawn_rate(&self) -> f32 {
+        2.0
+    }
+
+    fn enemy_strength(&self) -> f32 {
+        0.5
+    }
+}
+
+///强壮巢穴，生成速度慢，敌人较强
+#[derive(Debug, Component)]
+pub struct StrongLair {
+    enemy_spawner_container: EnemySpawnerContainer,
+    enabled: bool,
+    timer: Timer,
+}
+
+impl Default for StrongLair {
+    fn default() -> Self {
+        StrongLair {
+            enemy_spawner_container: EnemySpawnerContainer::new(),
+            enabled: true,
+            timer: spawn_timer(),
+        }
+    }
+}
+
+impl LairTrait for StrongLair {
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn enemy_spawner_container(&self) -> &EnemySpawnerContainer {
+        &self.enemy_spawner_container
+    }
+
+    fn timer(&self) -> &Timer {
+        &self.timer
+    }
+
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+
+    fn spawn_rate(&self) -> f32 {
+        0.5
+    }
+
+    fn enemy_strength(&self) -> f32 {
+        2.0
     }
 }
 
-fn larir_process(
+fn lair_process<T: LairTrait>(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    enemy_system_params: EnemySystemParams,
     map: Single<(Entity, &MapEnvironment)>,
-    mut lair_q: Query<(&mut SquareLarir, &Transform)>,
+    mut lair_q: Query<(&mut T, &mut LairWaves, &Transform, Entity)>,
     time: Res<Time>,
 ) {
     let (map_entity, map_environment) = map.into_inner();
 
     let mut commands = commands.entity(map_entity);
 
-    for (mut lair, transorm) in lair_q.iter_mut() {
-        lair.timer.tick(time.delta());
+    for (mut lair, mut lair_waves, transorm, entity) in lair_q.iter_mut() {
+        if !lair.is_enabled() {
+            continue;
+        }
+
+        for enemy in lair.tick(&mut lair_waves, time.delta(), map_environment) {
+            let Some(enemy_data) = enemy_system_params.get_enemy_data(&enemy) else {
+                tracing::error!("{} enemy data not found.", enemy);
+                continue;
+            };
 
-        if lair.timer.just_finished() && lair.enabled {
             lair.spaw_enemy(
                 &mut commands,
                 &asset_server,
                 transorm.translation,
-                "square",
+                &enemy_data,
                 map_environment,
             );
         }
+
+        if lair_waves.is_finished() {
+            commands.commands().entity(entity).insert(LairFinished);
+        }
+    }
+}
+
+fn collect_wave_state<T: LairTrait>(
+    mut wave_state: ResMut<WaveState>,
+    lair_q: Query<(&T, &LairWaves)>,
+) {
+    for (lair, lair_waves) in lair_q.iter() {
+        wave_state.merge(lair_waves, lair.time_to_next_wave(lair_waves));
     }
 }
 
@@ -104,24 +337,160 @@ pub struct LairPlugin<T> {
 
 impl<T: LairTrait> Plugin for LairPlugin<T> {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, larir_process);
+        app.add_systems(Update, lair_process::<T>);
+        app.add_systems(
+            Update,
+            collect_wave_state::<T>
+                .after(reset_wave_state)
+                .run_if(in_state(Screen::Gameplay)),
+        );
     }
 }
 
-pub fn spawn_lair(commands: &mut Commands, position: Vec3) -> Entity {
-    commands
-        .spawn((
-            SquareLarir::default(),
+pub trait LairFactory: 'static + Send + Sync + Debug {
+    fn spawn_lair(&self, commands: &mut EntityCommands);
+}
+
+#[derive(Debug)]
+pub struct DefaultLairFactory<T> {
+    _marker: PhantomData<T>,
+}
+
+impl<T> Default for DefaultLairFactory<T> {
+    fn default() -> Self {
+        DefaultLairFactory {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: LairTrait + Default> LairFactory for DefaultLairFactory<T> {
+    fn spawn_lair(&self, commands: &mut EntityCommands) {
+        commands.insert(T::default());
+    }
+}
+
+#[derive(Debug)]
+pub struct LairFactoryContainer(HashMap<String, Box<dyn LairFactory>>);
+
+impl Default for LairFactoryContainer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LairFactoryContainer {
+    pub fn new() -> Self {
+        let mut container = Self::empty();
+
+        container.register("square", DefaultLairFactory::<SquareLarir>::default());
+        container.register("fast", DefaultLairFactory::<FastLair>::default());
+        container.register("strong", DefaultLairFactory::<StrongLair>::default());
+
+        container
+    }
+
+    pub fn empty() -> Self {
+        Self(Default::default())
+    }
+
+    pub fn register<T: LairFactory>(&mut self, name: &str, factory: T) {
+        self.0.insert(name.to_string(), Box::new(factory));
+    }
+
+    pub fn spawn_lair(
+        &self,
+        commands: &mut Commands,
+        position: Vec3,
+        lair_item_name: &str,
+        waves: &[WaveData],
+    ) -> Option<Entity> {
+        let Some(factory) = self.0.get(lair_item_name) else {
+            tracing::error!("{} lair not match.", lair_item_name);
+            return None;
+        };
+
+        let mut entity_commands = commands.spawn((
+            LairWaves::new(waves.to_vec()),
             Lair,
-            Name::new("Lair"),
+            Name::new(format!("Lair({})", lair_item_name)),
             Transform {
                 translation: position,
                 ..default()
             },
-        ))
-        .id()
+        ));
+
+        factory.spawn_lair(&mut entity_commands);
+
+        Some(entity_commands.id())
+    }
 }
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins((LairPlugin::<SquareLarir>::default(),));
+    app.init_resource::<WaveState>();
+
+    app.add_plugins((
+        LairPlugin::<SquareLarir>::default(),
+        LairPlugin::<FastLair>::default(),
+        LairPlugin::<StrongLair>::default(),
+    ));
+
+    app.add_systems(Update, reset_wave_state.run_if(in_state(Screen::Gameplay)));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn waves() -> Vec<WaveData> {
+        vec![
+            WaveData {
+                delay: 1.0,
+                interval: 0.5,
+                enemies: vec![WaveEnemyData {
+                    name: "square".to_string(),
+                    count: 3,
+                }],
+            },
+            WaveData {
+                delay: 2.0,
+                interval: 0.0,
+                enemies: vec![WaveEnemyData {
+                    name: "fast".to_string(),
+                    count: 2,
+                }],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_lair_tick() {
+        let mut lair = SquareLarir::empty();
+        let mut lair_waves = LairWaves::new(waves());
+        let map_environment = MapEnvironment::default();
+
+        let tick = |lair: &mut SquareLarir, lair_waves: &mut LairWaves, delta: f32| {
+            lair.tick(lair_waves, Duration::from_secs_f32(delta), &map_environment)
+        };
+
+        assert!(tick(&mut lair, &mut lair_waves, 0.5).is_empty());
+        assert_eq!(lair.time_to_next_wave(&lair_waves), Some(0.5));
+
+        //超出的时间不会丢失，一帧内生成多个敌人
+        assert_eq!(tick(&mut lair, &mut lair_waves, 0.6).len(), 1);
+        assert_eq!(lair.time_to_next_wave(&lair_waves), None);
+        assert_eq!(tick(&mut lair, &mut lair_waves, 1.0).len(), 2);
+
+        //进入下一波次后重新等待波次的延迟
+        assert_eq!(lair_waves.current_wave(), 1);
+        assert_eq!(lair.time_to_next_wave(&lair_waves), Some(2.0));
+        assert!(tick(&mut lair, &mut lair_waves, 1.5).is_empty());
+
+        //间隔为0时不会无限生成
+        assert_eq!(tick(&mut lair, &mut lair_waves, 1.0), vec!["fast"]);
+        assert_eq!(tick(&mut lair, &mut lair_waves, 0.01), vec!["fast"]);
+        assert!(lair_waves.is_finished());
+        assert!(!lair.is_enabled());
+        assert!(tick(&mut lair, &mut lair_waves, 1.0).is_empty());
+    }
 }
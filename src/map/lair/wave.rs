@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+///波次中的一种敌人
+#[derive(Debug, Clone, Reflect, Deserialize, Serialize)]
+pub struct WaveEnemyData {
+    //敌人的名称，对应`EnemyDataAssets`中的敌人数据
+    pub name: String,
+    pub count: u32,
+}
+
+///一个波次
+#[derive(Debug, Clone, Reflect, Deserialize, Serialize)]
+pub struct WaveData {
+    //波次开始前的等待时间，单位为秒
+    pub delay: f32,
+    //生成敌人的间隔，单位为秒
+    pub interval: f32,
+    pub enemies: Vec<WaveEnemyData>,
+}
+
+impl WaveData {
+    pub fn enemy_count(&self) -> u32 {
+        self.enemies.iter().map(|enemy| enemy.count).sum()
+    }
+
+    ///按照顺序获取第index个敌人
+    pub fn get_enemy(&self, index: u32) -> Option<&str> {
+        let mut index = index;
+
+        for enemy in self.enemies.iter() {
+            if index < enemy.count {
+                return Some(&enemy.name);
+            }
+
+            index -= enemy.count;
+        }
+
+        None
+    }
+}
+
+///巢穴的波次进度，计时由巢穴负责
+#[derive(Debug, Component)]
+pub struct LairWaves {
+    waves: Vec<WaveData>,
+    current: usize,
+    spawned: u32,
+}
+
+impl LairWaves {
+    pub fn new(waves: Vec<WaveData>) -> Self {
+        LairWaves {
+            waves,
+            current: 0,
+            spawned: 0,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.waves.len()
+    }
+
+    ///当前波次，从0开始
+    pub fn current_wave(&self) -> usize {
+        self.current
+    }
+
+    pub fn wave_count(&self) -> usize {
+        self.waves.len()
+    }
+
+    ///当前波次是否已经开始生成敌人
+    pub fn is_spawning(&self) -> bool {
+        self.spawned > 0
+    }
+
+    ///生成下一个敌人前需要等待的时间，波次的第一个敌人等待波次的延迟，之后等待生成间隔
+    pub fn next_delay(&self) -> Option<f32> {
+        let wave = self.waves.get(self.current)?;
+
+        if self.spawned == 0 {
+            Some(wave.delay)
+        } else {
+            Some(wave.interval)
+        }
+    }
+
+    ///生成下一个敌人，当前波次生成完毕后进入下一波次
+    pub fn spawn_next(&mut self) -> Option<String> {
+        let wave = self.waves.get(self.current)?;
+        let enemy = wave.get_enemy(self.spawned).map(str::to_string);
+
+        self.spawned += 1;
+
+        if self.spawned >= wave.enemy_count() {
+            self.current += 1;
+            self.spawned = 0;
+        }
+
+        enemy
+    }
+}
+
+///所有巢穴的波次信息，供界面显示
+#[derive(Debug, Resource, Default)]
+pub struct WaveState {
+    //当前波次，从0开始
+    pub current_wave: usize,
+    pub wave_count: usize,
+    //距离下一波次开始的时间，单位为秒
+    pub time_to_next_wave: Option<f32>,
+}
+
+impl WaveState {
+    ///合并一个巢穴的波次信息
+    pub fn merge(&mut self, lair_waves: &LairWaves, time_to_next_wave: Option<f32>) {
+        self.current_wave = self.current_wave.max(lair_waves.current_wave());
+        self.wave_count = self.wave_count.max(lair_waves.wave_count());
+
+        if let Some(time) = time_to_next_wave {
+            self.time_to_next_wave =
+                Some(self.time_to_next_wave.map_or(time, |value| value.min(time)));
+        }
+    }
+}
+
+//每一帧重新收集所有巢穴的波次信息
+pub fn reset_wave_state(mut wave_state: ResMut<WaveState>) {
+    *wave_state = WaveState::default();
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LairWaves, WaveData, WaveEnemyData};
+
+    #[test]
+    fn test_lair_waves() {
+        let waves = vec![
+            WaveData {
+                delay: 1.0,
+                interval: 0.5,
+                enemies: vec![
+                    WaveEnemyData {
+                        name: "square".to_string(),
+                        count: 2,
+                    },
+                    WaveEnemyData {
+                        name: "fast".to_string(),
+                        count: 1,
+                    },
+                ],
+            },
+            WaveData {
+                delay: 2.0,
+                interval: 1.0,
+                enemies: vec![WaveEnemyData {
+                    name: "square".to_string(),
+                    count: 1,
+                }],
+            },
+        ];
+
+        let mut lair_waves = LairWaves::new(waves);
+
+        assert_eq!(lair_waves.next_delay(), Some(1.0));
+        assert_eq!(lair_waves.spawn_next().as_deref(), Some("square"));
+        assert!(lair_waves.is_spawning());
+
+        assert_eq!(lair_waves.next_delay(), Some(0.5));
+        assert_eq!(lair_waves.spawn_next().as_deref(), Some("square"));
+        assert_eq!(lair_waves.spawn_next().as_deref(), Some("fast"));
+
+        assert_eq!(lair_waves.current_wave(), 1);
+        assert!(!lair_waves.is_spawning());
+        assert_eq!(lair_waves.next_delay(), Some(2.0));
+        assert_eq!(lair_waves.spawn_next().as_deref(), Some("square"));
+
+        assert!(lair_waves.is_finished());
+        assert_eq!(lair_waves.next_delay(), None);
+        assert_eq!(lair_waves.spawn_next(), None);
+    }
+}
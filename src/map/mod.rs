@@ -5,26 +5,50 @@ mod tile;
 
 pub use environment::*;
 pub use item_data_factory::*;
+pub use lair::*;
 pub use tile::*;
 
-use std::fmt::Debug;
+use std::{collections::VecDeque, fmt::Debug};
 
-use bevy::prelude::*;
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt, LoadContext, io::Reader},
+    ecs::{entity::Entities, system::SystemParam},
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use vleue_navigator::prelude::NavMeshSettings;
 
 use crate::{
     MainCamera,
+    asset_tracking::LoadResource,
+    camera::{PanCam, PanCamClampBounds},
+    common::LightSource,
     consts::{
         MAP_ITEM_SELECTED_SIZE, MAP_LAYER, MAP_TIEM_SIZE, MAP_TILE_LAYER, MAP_TILE_SELECTED_LAYER,
     },
-    map::lair::spawn_lair,
+    level::Level,
+    navigator::{NavMeshBounds, NavigatorMode},
     screens::Screen,
     unit::{UnitData, UnitSystemParams},
 };
 
-#[derive(Debug, Clone, Default)]
+///可以放置时选择框的颜色
+const PLACEABLE_COLOR: Color = Color::srgb(0.4, 1.0, 0.4);
+///无法放置时选择框的颜色
+const UNPLACEABLE_COLOR: Color = Color::srgb(1.0, 0.3, 0.3);
+
+#[derive(Debug, Clone, Default, Reflect, Deserialize, Serialize)]
 pub struct MapItemData {
     name: String,
+    #[serde(default)]
     unit_item_name: String,
+    #[serde(default)]
+    lair_item_name: String,
+    //巢穴的波次
+    #[serde(default)]
+    waves: Vec<WaveData>,
 
     x: i32,
     y: i32,
@@ -40,74 +64,186 @@ impl MapItemData {
     }
 }
 
-#[derive(Debug, Resource)]
-pub struct MapData {
-    items: Vec<MapItemData>,
+///环境属性的初始值
+#[derive(Debug, Clone, Reflect, Deserialize, Serialize)]
+pub struct MapEnvironmentData {
+    pub name: String,
+    pub value: f32,
 }
 
-#[derive(Debug, Resource, Default)]
-pub struct MapState {
-    pub selelcted_map_item_data: Option<MapItemData>,
-    pub enable: bool,
+///环境属性随时间的变化
+#[derive(Debug, Clone, Reflect, Deserialize, Serialize)]
+pub struct MapEnvironmentModifierData {
+    //生效的时间，单位为秒
+    pub time: f32,
+    pub name: String,
+    pub value: f32,
+    //是否按照基础值的百分比修改
+    #[serde(default)]
+    pub percentage: bool,
 }
 
-impl Default for MapData {
-    fn default() -> Self {
-        let mut items = vec![];
+///环境目标的比较方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MapEnvironmentGoalComparison {
+    //属性值不高于阈值
+    Below,
+    //属性值不低于阈值
+    Above,
+}
 
-        for i in -2..2 {
-            items.push(MapItemData {
-                name: "hill".to_string(),
-                x: i,
-                y: 2,
-                ..default()
-            });
-        }
+///环境目标，环境属性达到阈值时胜利
+#[derive(Debug, Clone, Reflect, Deserialize, Serialize)]
+pub struct MapEnvironmentGoalData {
+    pub name: String,
+    pub comparison: MapEnvironmentGoalComparison,
+    pub threshold: i32,
+}
 
-        for i in -2..2 {
-            items.push(MapItemData {
-                name: "hill".to_string(),
-                x: i,
-                y: -2,
-                ..default()
-            });
-        }
+impl MapEnvironmentGoalData {
+    pub fn is_reached(&self, environment: &MapEnvironment) -> bool {
+        let value = environment.get_property(&self.name);
 
-        for i in -2..2 {
-            items.push(MapItemData {
-                name: "hill".to_string(),
-                x: 2,
-                y: i,
-                ..default()
-            });
+        match self.comparison {
+            MapEnvironmentGoalComparison::Below => value <= self.threshold,
+            MapEnvironmentGoalComparison::Above => value >= self.threshold,
         }
+    }
+}
 
-        items.push(MapItemData {
-            name: "unit".to_string(),
-            unit_item_name: "bonfire".to_string(),
-            x: 0,
-            y: 0,
-            ..default()
-        });
+///地图数据，从`*.map.yaml`中加载
+#[derive(Debug, Clone, Asset, Reflect, Deserialize, Serialize)]
+pub struct MapData {
+    //地图的宽，单位为格
+    pub x: i32,
+    //地图的高，单位为格
+    pub y: i32,
+    pub background: String,
+    pub items: Vec<MapItemData>,
+    #[serde(default)]
+    pub environment: Vec<MapEnvironmentData>,
+    #[serde(default)]
+    pub environment_modifiers: Vec<MapEnvironmentModifierData>,
+    //坚持指定的时间后胜利，单位为秒
+    #[serde(default)]
+    pub survival_time: Option<f32>,
+    //寻路的方式
+    #[serde(default)]
+    pub navigator_mode: NavigatorMode,
+    //环境属性达到阈值后胜利
+    #[serde(default)]
+    pub environment_goal: Option<MapEnvironmentGoalData>,
+    //下一关的地图
+    #[serde(default)]
+    pub next_map: Option<String>,
+}
+
+impl MapData {
+    pub fn get_background_image(&self, asset_server: &AssetServer) -> Handle<Image> {
+        asset_server.load(&format!("images/map/{}.png", self.background))
+    }
+
+    pub fn get_map_size(&self) -> Vec2 {
+        get_map_size(self.x, self.y)
+    }
+}
+
+#[derive(Default, TypePath)]
+pub struct MapDataLoader;
+
+#[derive(Debug, Error)]
+pub enum MapDataLoaderError {
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse Yaml: {0}")]
+    Yaml(#[from] yaml_serde::Error),
+}
+
+impl AssetLoader for MapDataLoader {
+    type Asset = MapData;
+
+    type Settings = ();
+
+    type Error = MapDataLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut buffer = String::new();
+        reader.read_to_string(&mut buffer).await?;
+        let asset: MapData = yaml_serde::from_str(&buffer)?;
+        Ok(asset)
+    }
+    fn extensions(&self) -> &[&str] {
+        &[".map.yaml"]
+    }
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct MapDataAssets {
+    assets: HashMap<String, Handle<MapData>>,
+}
+
+impl FromWorld for MapDataAssets {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let mut assets = HashMap::new();
+
+        assets.insert("level1".into(), asset_server.load("map/level1.map.yaml"));
+        assets.insert("level2".into(), asset_server.load("map/level2.map.yaml"));
+
+        Self { assets }
+    }
+}
+
+#[derive(SystemParam)]
+pub struct MapSystemParams<'w> {
+    map_data_assets: Res<'w, MapDataAssets>,
+    map_data_set: Res<'w, Assets<MapData>>,
+}
 
-        MapData { items }
+impl MapSystemParams<'_> {
+    pub fn get_map_data(&self, map_name: &str) -> Option<MapData> {
+        self.map_data_assets
+            .assets
+            .get(map_name)
+            .and_then(|handle| self.map_data_set.get(handle).cloned())
     }
 }
 
+#[derive(Debug, Resource, Default)]
+pub struct MapState {
+    pub selelcted_map_item_data: Option<MapItemData>,
+    pub enable: bool,
+}
+
 #[derive(Debug, Component, Default)]
 pub struct MapPosition {
     x: i32,
     y: i32,
 }
 
+impl MapPosition {
+    pub fn position_i(&self) -> IVec2 {
+        IVec2::new(self.x, self.y)
+    }
+}
+
 fn on_spawn_unit(
     mouse_button_input: Res<ButtonInput<MouseButton>>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    map: Single<(Entity, &Map)>,
+    map: Single<(Entity, &mut Map)>,
     map_positon: Single<&MapPosition>,
     mut map_state: ResMut<MapState>,
+    mut level: ResMut<Level>,
     unit_system_params: UnitSystemParams,
+    map_path_params: MapPathParams,
 ) {
     if map_state.enable
         && map_state.selelcted_map_item_data.is_some()
@@ -117,13 +253,46 @@ fn on_spawn_unit(
         map_item_data.x = map_positon.x;
         map_item_data.y = map_positon.y;
 
-        let position = get_item_position(map_item_data.x, map_item_data.y).extend(0.0);
+        let (map_entity, mut map) = map.into_inner();
 
-        let (map_entity, map) = map.into_inner();
+        if !map.can_place(map_positon.position_i()) {
+            tracing::info!(
+                "Can not place {} at {:?}.",
+                map_item_data.unit_item_name,
+                map_positon.position_i()
+            );
+            return;
+        }
+
+        if map.is_obstacle(&map_item_data, &unit_system_params)
+            && map_path_params.would_cut_off(&map, map_positon.position_i())
+        {
+            tracing::info!(
+                "Can not place {} at {:?}, it would cut off every lair from the light sources.",
+                map_item_data.unit_item_name,
+                map_positon.position_i()
+            );
+            return;
+        }
+
+        let cost = unit_system_params
+            .get_unit_data(&map_item_data.unit_item_name)
+            .map(|unit_data| unit_data.cost)
+            .unwrap_or_default();
+
+        if !level.can_afford(cost) {
+            tracing::info!(
+                "Not enough sunlight to place {}.",
+                map_item_data.unit_item_name
+            );
+            return;
+        }
+
+        let position = get_item_position(map_item_data.x, map_item_data.y).extend(MAP_TILE_LAYER);
 
         let mut commands = commands.entity(map_entity);
 
-        map.item_factory_container.spawn_map_item(
+        let spawned = map.spawn_map_item(
             &mut commands,
             &asset_server,
             &map_item_data,
@@ -131,6 +300,11 @@ fn on_spawn_unit(
             &unit_system_params,
         );
 
+        if spawned.is_some() {
+            level.spend_sunlight(cost);
+            level.record_unit_placed();
+        }
+
         map_state.enable = false;
         map_state.selelcted_map_item_data = None;
     }
@@ -171,30 +345,302 @@ fn update_map_position(
     map_position.1.y = position_i.y;
 }
 
+//根据能否放置更新选择框的颜色
+fn update_map_position_color(
+    map_position: Single<(&mut Sprite, &MapPosition)>,
+    map: Single<&Map>,
+    map_state: Res<MapState>,
+    unit_system_params: UnitSystemParams,
+    map_path_params: MapPathParams,
+) {
+    let (mut sprite, map_position) = map_position.into_inner();
+
+    let color = match map_state.selelcted_map_item_data.as_ref() {
+        None => Color::WHITE,
+        Some(item_data) => {
+            let position = map_position.position_i();
+            let cut_off = map.is_obstacle(item_data, &unit_system_params)
+                && map_path_params.would_cut_off(&map, position);
+
+            if map.can_place(position) && !cut_off {
+                PLACEABLE_COLOR
+            } else {
+                UNPLACEABLE_COLOR
+            }
+        }
+    };
+
+    if sprite.color != color {
+        sprite.color = color;
+    }
+}
+
+//释放已被销毁的物品占用的格子，只在有格子被释放时标记地图的变化
+fn release_map_tiles(mut map: Single<&mut Map>, entities: &Entities) {
+    if map.has_released(entities) {
+        map.release(entities);
+    }
+}
+
+///检查放置是否会阻断敌人的路径
+#[derive(SystemParam)]
+pub struct MapPathParams<'w, 's> {
+    lair_query: Query<'w, 's, &'static GlobalTransform, With<Lair>>,
+    light_source_query: Query<'w, 's, &'static GlobalTransform, With<LightSource>>,
+}
+
+impl MapPathParams<'_, '_> {
+    ///在格子上放置障碍后，是否所有巢穴都无法到达任何光源
+    pub fn would_cut_off(&self, map: &Map, position: IVec2) -> bool {
+        let lairs = self
+            .lair_query
+            .iter()
+            .map(|transform| get_position_i(transform.translation().truncate()))
+            .collect::<Vec<_>>();
+        let light_sources = self
+            .light_source_query
+            .iter()
+            .map(|transform| get_position_i(transform.translation().truncate()))
+            .collect::<Vec<_>>();
+
+        map.would_cut_off(position, &lairs, &light_sources)
+    }
+}
+
 #[derive(Debug, Component)]
 pub struct Map {
     item_factory_container: MapItemFactoryContainer,
     x: i32,
     y: i32,
+    //被占用的格子
+    occupied: HashMap<IVec2, Entity>,
+    //阻挡路径的格子
+    blocked: HashSet<IVec2>,
+    //障碍变化的格子，按照变化的顺序记录，用于增量更新流场
+    obstacle_changes: Vec<IVec2>,
 }
 
-impl Default for Map {
-    fn default() -> Self {
+impl Map {
+    pub fn from_data(data: &MapData) -> Self {
         Map {
             item_factory_container: Default::default(),
-            x: 25,
-            y: 25,
+            x: data.x,
+            y: data.y,
+            occupied: Default::default(),
+            blocked: Default::default(),
+            obstacle_changes: vec![],
         }
     }
-}
 
-impl Map {
+    ///格子是否在地图内
+    pub fn contains(&self, position: IVec2) -> bool {
+        let min_x = -self.x / 2;
+        let min_y = -self.y / 2;
+
+        position.x >= min_x
+            && position.x < min_x + self.x
+            && position.y >= min_y
+            && position.y < min_y + self.y
+    }
+
+    ///地图左下角的格子和地图的格子数量
+    pub fn get_tile_bounds(&self) -> (IVec2, IVec2) {
+        (
+            IVec2::new(-self.x / 2, -self.y / 2),
+            IVec2::new(self.x, self.y),
+        )
+    }
+
+    pub fn is_blocked(&self, position: IVec2) -> bool {
+        self.blocked.contains(&position)
+    }
+
+    ///障碍变化时增加
+    pub fn obstacle_version(&self) -> u32 {
+        self.obstacle_changes.len() as u32
+    }
+
+    ///指定版本之后障碍变化的格子
+    pub fn obstacle_changes_since(&self, version: u32) -> &[IVec2] {
+        self.obstacle_changes
+            .get(version as usize..)
+            .unwrap_or_default()
+    }
+
+    pub fn get_occupant(&self, position: IVec2) -> Option<Entity> {
+        self.occupied.get(&position).copied()
+    }
+
+    pub fn can_place(&self, position: IVec2) -> bool {
+        self.contains(position) && !self.occupied.contains_key(&position)
+    }
+
+    pub fn is_obstacle(
+        &self,
+        item_data: &MapItemData,
+        unit_system_params: &UnitSystemParams,
+    ) -> bool {
+        self.item_factory_container
+            .is_obstacle(item_data, unit_system_params)
+    }
+
+    ///在格子上放置障碍后，是否所有巢穴都无法到达任何光源
+    ///放置前已经无法到达时不阻止放置
+    pub fn would_cut_off(&self, position: IVec2, lairs: &[IVec2], light_sources: &[IVec2]) -> bool {
+        if lairs.is_empty() || light_sources.is_empty() {
+            return false;
+        }
+
+        if !self.is_connected(lairs, light_sources, None) {
+            return false;
+        }
+
+        !self.is_connected(lairs, light_sources, Some(position))
+    }
+
+    //巢穴和光源之间是否存在不经过障碍的路径，只能上下左右移动
+    fn is_connected(&self, lairs: &[IVec2], light_sources: &[IVec2], extra: Option<IVec2>) -> bool {
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+
+        for lair in lairs.iter() {
+            if visited.insert(*lair) {
+                queue.push_back(*lair);
+            }
+        }
+
+        while let Some(current) = queue.pop_front() {
+            if light_sources.contains(&current) {
+                return true;
+            }
+
+            for offset in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+                let next = current + offset;
+
+                if visited.contains(&next) || !self.contains(next) {
+                    continue;
+                }
+
+                //光源所在的格子总是可以到达
+                let blocked = self.blocked.contains(&next) || extra == Some(next);
+                if blocked && !light_sources.contains(&next) {
+                    continue;
+                }
+
+                visited.insert(next);
+                queue.push_back(next);
+            }
+        }
+
+        false
+    }
+
+    //是否有格子被已销毁的物品占用
+    fn has_released(&self, entities: &Entities) -> bool {
+        self.occupied
+            .values()
+            .any(|entity| !entities.contains(*entity))
+    }
+
+    //释放已被销毁的物品占用的格子
+    fn release(&mut self, entities: &Entities) {
+        self.occupied.retain(|_, entity| entities.contains(*entity));
+
+        let occupied = &self.occupied;
+        let obstacle_changes = &mut self.obstacle_changes;
+        self.blocked.retain(|position| {
+            let retained = occupied.contains_key(position);
+            if !retained {
+                obstacle_changes.push(*position);
+            }
+            retained
+        });
+    }
+
+    pub fn spawn_map_item(
+        &mut self,
+        commands: &mut EntityCommands,
+        asset_server: &AssetServer,
+        item_data: &MapItemData,
+        position: Vec3,
+        unit_system_params: &UnitSystemParams,
+    ) -> Option<Entity> {
+        let position_i = IVec2::new(item_data.x, item_data.y);
+
+        if !self.can_place(position_i) {
+            tracing::warn!("{} can not be placed at {:?}.", item_data.name, position_i);
+            return None;
+        }
+
+        let entity = self.item_factory_container.spawn_map_item(
+            commands,
+            asset_server,
+            item_data,
+            position,
+            unit_system_params,
+        )?;
+
+        self.occupied.insert(position_i, entity);
+
+        if self.is_obstacle(item_data, unit_system_params) {
+            self.blocked.insert(position_i);
+            self.obstacle_changes.push(position_i);
+        }
+
+        Some(entity)
+    }
+
     pub fn get_map_size(&self) -> Vec2 {
-        let x = self.x as f32 * MAP_TIEM_SIZE;
-        let y = self.y as f32 * MAP_TIEM_SIZE;
+        get_map_size(self.x, self.y)
+    }
+}
+
+//地图的像素大小
+fn get_map_size(x: i32, y: i32) -> Vec2 {
+    let x = x as f32 * MAP_TIEM_SIZE;
+    let y = y as f32 * MAP_TIEM_SIZE;
 
-        Vec2 { x, y }
+    Vec2 { x, y }
+}
+
+//地图大小变化时，更新导航网格和相机的边界
+fn update_map_bounds(
+    mut commands: Commands,
+    map: Single<&Map, Changed<Map>>,
+    mut nav_mesh_query: Query<(&mut NavMeshSettings, &mut NavMeshBounds)>,
+    mut camera_query: Query<(Entity, &mut PanCam), With<MainCamera>>,
+) {
+    let size = map.get_map_size();
+
+    for (mut settings, mut bounds) in nav_mesh_query.iter_mut() {
+        if bounds.size != size {
+            bounds.size = size;
+            settings.fixed = bounds.get_outer_edges();
+        }
     }
+
+    let half_size = size / 2.0;
+
+    for (entity, mut pan_cam) in camera_query.iter_mut() {
+        if pan_cam.max_x == half_size.x && pan_cam.max_y == half_size.y {
+            continue;
+        }
+
+        pan_cam.min_x = -half_size.x;
+        pan_cam.max_x = half_size.x;
+        pan_cam.min_y = -half_size.y;
+        pan_cam.max_y = half_size.y;
+
+        commands.trigger(PanCamClampBounds { entity });
+    }
+}
+
+//世界坐标所在的格子
+pub fn get_position_i(position: Vec2) -> IVec2 {
+    get_item_position_i(
+        position.x + MAP_TIEM_SIZE / 2.0,
+        position.y + MAP_TIEM_SIZE / 2.0,
+    )
 }
 
 fn get_item_position_i(x: f32, y: f32) -> IVec2 {
@@ -204,7 +650,7 @@ fn get_item_position_i(x: f32, y: f32) -> IVec2 {
     IVec2 { x, y }
 }
 
-fn get_item_position(x: i32, y: i32) -> Vec2 {
+pub fn get_item_position(x: i32, y: i32) -> Vec2 {
     let x = 1.0 + x as f32 * MAP_TIEM_SIZE;
     let y = -1.0 + y as f32 * MAP_TIEM_SIZE;
 
@@ -220,15 +666,15 @@ pub fn spawn_map(
     map_data: &MapData,
     unit_system_params: &UnitSystemParams,
 ) {
-    let map = Map::default();
+    let mut map = Map::from_data(map_data);
 
-    let image = asset_server.load("images/map/bg.png");
+    let image = map_data.get_background_image(asset_server);
 
     let mut commands = command.spawn((
         Name::new("Map"),
         DespawnOnExit(Screen::Gameplay),
         Visibility::Visible,
-        MapEnvironment::default(),
+        MapEnvironment::from_data(&map_data.environment, &map_data.environment_modifiers),
         Sprite {
             image: image,
             custom_size: Some(map.get_map_size()),
@@ -253,7 +699,7 @@ pub fn spawn_map(
         let item = item.clone();
         let position = get_item_position(item.x, item.y).extend(MAP_TILE_LAYER);
 
-        map.item_factory_container.spawn_map_item(
+        map.spawn_map_item(
             &mut commands,
             asset_server,
             &item,
@@ -278,32 +724,96 @@ pub fn spawn_map(
         },
     ));
 
-    spawn_lair_(&mut commands, 10, -10);
-
     commands.insert(map);
 }
 
-fn spawn_lair_(commands: &mut EntityCommands, x: i32, y: i32) {
-    let lair = {
-        let position = get_item_position(x, y).extend(MAP_TILE_LAYER);
-
-        let mut commands = commands.commands();
-        spawn_lair(&mut commands, position)
-    };
-
-    commands.add_child(lair);
-
-    commands.add_child(lair);
-}
-
 pub(super) fn plugin(app: &mut App) {
-    app.init_resource::<MapData>();
     app.init_resource::<MapState>();
+    app.init_asset_loader::<MapDataLoader>();
+    app.init_asset::<MapData>();
+
+    app.load_resource::<MapDataAssets>();
 
     app.add_plugins(lair::plugin);
 
     app.add_systems(
         Update,
-        (update_map_position, on_spawn_unit).run_if(in_state(Screen::Gameplay)),
+        (
+            update_map_position,
+            update_map_position_color,
+            on_spawn_unit,
+            release_map_tiles,
+            update_map_bounds,
+            update_map_environment,
+            apply_environment_modifiers,
+        )
+            .run_if(in_state(Screen::Gameplay)),
     );
 }
+
+#[cfg(test)]
+mod test {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn test_would_cut_off() {
+        let mut map = Map {
+            item_factory_container: MapItemFactoryContainer::empty(),
+            x: 5,
+            y: 5,
+            occupied: Default::default(),
+            blocked: Default::default(),
+            obstacle_changes: vec![],
+        };
+
+        //只留下一个缺口的墙
+        for y in -2..2 {
+            map.blocked.insert(IVec2::new(0, y));
+        }
+
+        let lairs = [IVec2::new(-2, 0)];
+        let light_sources = [IVec2::new(2, 0)];
+
+        assert!(map.would_cut_off(IVec2::new(0, 2), &lairs, &light_sources));
+        assert!(!map.would_cut_off(IVec2::new(1, 1), &lairs, &light_sources));
+        assert!(!map.would_cut_off(IVec2::new(0, 2), &[], &light_sources));
+
+        //已经无法到达时不阻止放置
+        map.blocked.insert(IVec2::new(0, 2));
+        assert!(!map.would_cut_off(IVec2::new(1, 1), &lairs, &light_sources));
+    }
+
+    #[test]
+    fn test_release_map_tiles() {
+        let mut world = World::new();
+        let unit = world.spawn_empty().id();
+
+        world.spawn(Map {
+            item_factory_container: MapItemFactoryContainer::empty(),
+            x: 5,
+            y: 5,
+            occupied: [(IVec2::ZERO, unit)].into_iter().collect(),
+            blocked: [IVec2::ZERO].into_iter().collect(),
+            obstacle_changes: vec![],
+        });
+        world.clear_trackers();
+
+        let mut changed_q = world.query_filtered::<&Map, Changed<Map>>();
+
+        //没有格子被释放时地图不变化
+        world.run_system_once(release_map_tiles).unwrap();
+        assert_eq!(changed_q.iter(&world).count(), 0);
+
+        world.clear_trackers();
+        world.despawn(unit);
+        world.run_system_once(release_map_tiles).unwrap();
+
+        let map = changed_q.single(&world).unwrap();
+        assert!(map.can_place(IVec2::ZERO));
+        assert!(!map.is_blocked(IVec2::ZERO));
+        assert_eq!(map.obstacle_version(), 1);
+        assert_eq!(map.obstacle_changes_since(0), &[IVec2::ZERO]);
+    }
+}
@@ -13,24 +13,31 @@ pub fn spawn_hill_map_item(
     asset_server: &AssetServer,
     _map_item_data: &MapItemData,
     position: Vec3,
-) {
+) -> Entity {
     let image = asset_server.load("images/map/Hill.png");
 
-    commands.with_child((
-        Hill,
-        Sprite {
-            image,
-            custom_size: Some(Vec2::splat(MAP_ITEM_CONTENT_SIZE)),
-            ..default()
-        },
-        Transform {
-            translation: position,
-            ..default()
-        },
-        Obstacle::Wall,
-        RigidBody::Static,
-        Collider::rectangle(MAP_ITEM_CONTENT_SIZE, MAP_ITEM_CONTENT_SIZE),
-        GameLayer::default_layers(),
-        Name::new("Hill")
-    ));
+    let hill = commands
+        .commands()
+        .spawn((
+            Hill,
+            Sprite {
+                image,
+                custom_size: Some(Vec2::splat(MAP_ITEM_CONTENT_SIZE)),
+                ..default()
+            },
+            Transform {
+                translation: position,
+                ..default()
+            },
+            Obstacle::Wall,
+            RigidBody::Static,
+            Collider::rectangle(MAP_ITEM_CONTENT_SIZE, MAP_ITEM_CONTENT_SIZE),
+            GameLayer::default_layers(),
+            Name::new("Hill"),
+        ))
+        .id();
+
+    commands.add_child(hill);
+
+    hill
 }
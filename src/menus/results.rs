@@ -0,0 +1,69 @@
+//! The results menu shown when a level ends.
+
+use bevy::prelude::*;
+
+use crate::{
+    level::{Level, LevelOutcome},
+    menus::Menu,
+    screens::Screen,
+    theme::widget,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Menu::Results), spawn_results_menu);
+}
+
+fn spawn_results_menu(mut commands: Commands, level: Res<Level>) {
+    let header = match level.outcome() {
+        Some(LevelOutcome::Victory) => "Victory",
+        Some(LevelOutcome::Defeat) => "Defeat",
+        None => "Level finished",
+    };
+
+    let stats = level.stats();
+    let show_next_level = level.outcome() == Some(LevelOutcome::Victory) && level.has_next_map();
+
+    commands
+        .spawn((
+            widget::ui_root("Results Menu"),
+            GlobalZIndex(2),
+            DespawnOnExit(Menu::Results),
+        ))
+        .with_children(|parent| {
+            parent.spawn(widget::header(header));
+            parent.spawn(widget::label(format!("Time: {:.0}s", stats.elapsed)));
+            parent.spawn(widget::label(format!(
+                "Sunlight collected: {}",
+                stats.sunlight_collected
+            )));
+            parent.spawn(widget::label(format!(
+                "Units placed: {}",
+                stats.units_placed
+            )));
+
+            if show_next_level {
+                parent.spawn(widget::button("Next level", next_level));
+            }
+
+            parent.spawn(widget::button("Retry", retry));
+            parent.spawn(widget::button("Quit to title", quit_to_title));
+        });
+}
+
+fn retry(_: On<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Loading);
+}
+
+fn next_level(
+    _: On<Pointer<Click>>,
+    mut level: ResMut<Level>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    if level.advance() {
+        next_screen.set(Screen::Loading);
+    }
+}
+
+fn quit_to_title(_: On<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
@@ -0,0 +1,559 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::LightSource,
+    map::{Map, get_position_i},
+    screens::Screen,
+};
+
+///寻路的方式，由地图文件选择
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource, Reflect, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NavigatorMode {
+    //每个敌人在导航网格上单独寻路
+    #[default]
+    NavMesh,
+    //所有敌人共享前往光源的流场
+    FlowField,
+}
+
+///流场最多覆盖的格子数量，超过时改为使用导航网格
+pub const MAX_FLOW_FIELD_TILES: i32 = 128 * 128;
+
+impl NavigatorMode {
+    ///地图的格子数量超过流场的上限时改为使用导航网格
+    pub fn for_map(self, x: i32, y: i32) -> Self {
+        if self == NavigatorMode::FlowField && x * y > MAX_FLOW_FIELD_TILES {
+            tracing::warn!(
+                "Map {}x{} is too large for flow fields, use nav mesh instead.",
+                x,
+                y
+            );
+            return NavigatorMode::NavMesh;
+        }
+
+        self
+    }
+}
+
+///前往一个格子的流场
+#[derive(Debug, Clone)]
+pub struct FlowField {
+    min: IVec2,
+    size: IVec2,
+    goal: IVec2,
+    //每个格子到目标的距离，无法到达时为u32::MAX
+    distances: Vec<u32>,
+    //每个格子前往的下一个格子
+    next: Vec<Option<IVec2>>,
+}
+
+const NEIGHBORS: [IVec2; 4] = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y];
+
+//按照距离从小到大处理的格子
+type DistanceQueue = BinaryHeap<Reverse<(u32, i32, i32)>>;
+
+impl FlowField {
+    pub fn build(min: IVec2, size: IVec2, goal: IVec2, is_blocked: impl Fn(IVec2) -> bool) -> Self {
+        let count = (size.x * size.y).max(0) as usize;
+
+        let mut field = FlowField {
+            min,
+            size,
+            goal,
+            distances: vec![u32::MAX; count],
+            next: vec![None; count],
+        };
+
+        //从目标开始计算每个格子到目标的距离
+        if let Some(goal_index) = field.index(goal) {
+            field.distances[goal_index] = 0;
+
+            let mut queue = DistanceQueue::new();
+            queue.push(Reverse((0, goal.x, goal.y)));
+            field.relax(queue, &is_blocked);
+        }
+
+        for y in 0..size.y {
+            for x in 0..size.x {
+                field.update_next(min + IVec2::new(x, y), &is_blocked);
+            }
+        }
+
+        field
+    }
+
+    ///障碍变化后只修复受影响的区域，返回重新计算方向的格子数量
+    ///
+    ///新的障碍使依赖它的格子失效，失效的格子和被移除的障碍从周围有效的格子重新计算距离，
+    ///最后只更新距离变化的格子和它们周围格子的方向
+    pub fn repair(&mut self, changes: &[IVec2], is_blocked: impl Fn(IVec2) -> bool) -> usize {
+        let mut changed = self.invalidate(changes, &is_blocked);
+
+        let mut queue = DistanceQueue::new();
+
+        for position in changes.iter().chain(changed.clone().iter()) {
+            let Some(index) = self.index(*position) else {
+                continue;
+            };
+
+            if *position == self.goal || is_blocked(*position) {
+                continue;
+            }
+
+            let distance = NEIGHBORS
+                .iter()
+                .filter(|offset| !is_blocked(*position + **offset))
+                .filter_map(|offset| self.distance(*position + *offset))
+                .min()
+                .map_or(u32::MAX, |distance| distance + 1);
+
+            if distance < self.distances[index] {
+                self.distances[index] = distance;
+                queue.push(Reverse((distance, position.x, position.y)));
+                changed.push(*position);
+            }
+        }
+
+        changed.extend(self.relax(queue, &is_blocked));
+        changed.extend_from_slice(changes);
+
+        //距离或者障碍变化的格子会影响周围格子的方向
+        let mut updated = HashSet::new();
+
+        for position in changed {
+            for y in -1..=1 {
+                for x in -1..=1 {
+                    let neighbor = position + IVec2::new(x, y);
+
+                    if self.index(neighbor).is_some() && updated.insert(neighbor) {
+                        self.update_next(neighbor, &is_blocked);
+                    }
+                }
+            }
+        }
+
+        updated.len()
+    }
+
+    //新的障碍和失去支撑的格子变为无法到达，返回失效的格子
+    fn invalidate(&mut self, changes: &[IVec2], is_blocked: &impl Fn(IVec2) -> bool) -> Vec<IVec2> {
+        let mut invalidated = vec![];
+        let mut queue = DistanceQueue::new();
+
+        for position in changes.iter() {
+            let Some(distance) = self.distance(*position) else {
+                continue;
+            };
+
+            if is_blocked(*position) && *position != self.goal {
+                queue.push(Reverse((distance, position.x, position.y)));
+            }
+        }
+
+        while let Some(Reverse((distance, x, y))) = queue.pop() {
+            let position = IVec2::new(x, y);
+
+            if self.distance(position) != Some(distance) {
+                continue;
+            }
+
+            //仍然有相邻的格子距离更近时保持不变
+            let supported = NEIGHBORS.iter().any(|offset| {
+                let neighbor = position + *offset;
+                !is_blocked(neighbor) && self.distance(neighbor) == Some(distance.wrapping_sub(1))
+            });
+
+            if !is_blocked(position) && supported {
+                continue;
+            }
+
+            let index = self.index(position).unwrap();
+            self.distances[index] = u32::MAX;
+            invalidated.push(position);
+
+            //依赖这个格子的相邻格子需要重新检查
+            for offset in NEIGHBORS {
+                let neighbor = position + offset;
+
+                if neighbor != self.goal && self.distance(neighbor) == Some(distance + 1) {
+                    queue.push(Reverse((distance + 1, neighbor.x, neighbor.y)));
+                }
+            }
+        }
+
+        invalidated
+    }
+
+    //从队列中的格子向外更新距离，返回距离变小的格子
+    fn relax(
+        &mut self,
+        mut queue: DistanceQueue,
+        is_blocked: &impl Fn(IVec2) -> bool,
+    ) -> Vec<IVec2> {
+        let mut changed = vec![];
+
+        while let Some(Reverse((distance, x, y))) = queue.pop() {
+            let current = IVec2::new(x, y);
+
+            if self.distance(current) != Some(distance) {
+                continue;
+            }
+
+            for offset in NEIGHBORS {
+                let neighbor = current + offset;
+
+                let Some(index) = self.index(neighbor) else {
+                    continue;
+                };
+
+                if self.distances[index] <= distance + 1 || is_blocked(neighbor) {
+                    continue;
+                }
+
+                self.distances[index] = distance + 1;
+                queue.push(Reverse((distance + 1, neighbor.x, neighbor.y)));
+                changed.push(neighbor);
+            }
+        }
+
+        changed
+    }
+
+    //格子指向距离最小的相邻格子，斜向移动时不能穿过障碍的角
+    fn update_next(&mut self, position: IVec2, is_blocked: &impl Fn(IVec2) -> bool) {
+        let index = self.index(position).unwrap();
+
+        if position == self.goal {
+            self.next[index] = Some(self.goal);
+            return;
+        }
+
+        let mut best = None;
+        let mut best_distance = self.distances[index];
+
+        for offset_y in -1..=1 {
+            for offset_x in -1..=1 {
+                let offset = IVec2::new(offset_x, offset_y);
+                if offset == IVec2::ZERO {
+                    continue;
+                }
+
+                let neighbor = position + offset;
+                let Some(neighbor_index) = self.index(neighbor) else {
+                    continue;
+                };
+
+                if offset_x != 0
+                    && offset_y != 0
+                    && (is_blocked(position + IVec2::new(offset_x, 0))
+                        || is_blocked(position + IVec2::new(0, offset_y)))
+                {
+                    continue;
+                }
+
+                if self.distances[neighbor_index] < best_distance {
+                    best_distance = self.distances[neighbor_index];
+                    best = Some(neighbor);
+                }
+            }
+        }
+
+        self.next[index] = best;
+    }
+
+    //可以到达的格子到目标的距离
+    fn distance(&self, position: IVec2) -> Option<u32> {
+        self.index(position)
+            .map(|index| self.distances[index])
+            .filter(|distance| *distance != u32::MAX)
+    }
+
+    fn index(&self, position: IVec2) -> Option<usize> {
+        let local = position - self.min;
+
+        if local.x < 0 || local.y < 0 || local.x >= self.size.x || local.y >= self.size.y {
+            return None;
+        }
+
+        Some((local.y * self.size.x + local.x) as usize)
+    }
+
+    pub fn goal(&self) -> IVec2 {
+        self.goal
+    }
+
+    ///格子前往的下一个格子，无法到达目标时返回None
+    pub fn next(&self, position: IVec2) -> Option<IVec2> {
+        self.index(position).and_then(|index| self.next[index])
+    }
+}
+
+///每个光源的流场
+#[derive(Debug, Resource, Default)]
+pub struct FlowFields {
+    fields: HashMap<Entity, FlowField>,
+    //构建流场时地图障碍的版本
+    obstacle_version: u32,
+}
+
+impl FlowFields {
+    pub fn get(&self, light_source: Entity) -> Option<&FlowField> {
+        self.fields.get(&light_source)
+    }
+
+    ///敌人所在格子前往的下一个格子
+    pub fn next(&self, light_source: Entity, position: Vec3) -> Option<IVec2> {
+        self.get(light_source)
+            .and_then(|field| field.next(get_position_i(position.truncate())))
+    }
+
+    ///构建流场时地图障碍的版本
+    pub fn obstacle_version(&self) -> u32 {
+        self.obstacle_version
+    }
+
+    ///光源变化时构建对应的流场，障碍变化时只修复流场中受影响的区域，返回构建或修复的流场数量
+    ///
+    ///obstacle_changes为`obstacle_version`之后障碍变化的格子
+    pub fn update(
+        &mut self,
+        min: IVec2,
+        size: IVec2,
+        obstacle_version: u32,
+        obstacle_changes: &[IVec2],
+        light_sources: &[(Entity, IVec2)],
+        is_blocked: impl Fn(IVec2) -> bool,
+    ) -> usize {
+        self.fields.retain(|entity, _| {
+            light_sources
+                .iter()
+                .any(|(light_source, _)| light_source == entity)
+        });
+
+        let mut count = 0;
+
+        for (entity, goal) in light_sources.iter() {
+            match self.fields.get_mut(entity) {
+                Some(field) if field.goal == *goal && field.min == min && field.size == size => {
+                    if !obstacle_changes.is_empty() {
+                        field.repair(obstacle_changes, &is_blocked);
+                        count += 1;
+                    }
+                }
+                _ => {
+                    let field = FlowField::build(min, size, *goal, &is_blocked);
+                    self.fields.insert(*entity, field);
+                    count += 1;
+                }
+            }
+        }
+
+        self.obstacle_version = obstacle_version;
+
+        count
+    }
+}
+
+fn update_flow_fields(
+    map: Single<&Map>,
+    mut flow_fields: ResMut<FlowFields>,
+    light_source_query: Query<(Entity, &GlobalTransform), With<LightSource>>,
+) {
+    let light_sources = light_source_query
+        .iter()
+        .map(|(entity, transform)| (entity, get_position_i(transform.translation().truncate())))
+        .collect::<Vec<_>>();
+
+    //地图重新生成后障碍的版本变小，重新构建所有流场
+    if map.obstacle_version() < flow_fields.obstacle_version() {
+        *flow_fields = FlowFields::default();
+    }
+
+    let (min, size) = map.get_tile_bounds();
+    let obstacle_changes = map.obstacle_changes_since(flow_fields.obstacle_version());
+
+    flow_fields.update(
+        min,
+        size,
+        map.obstacle_version(),
+        obstacle_changes,
+        &light_sources,
+        |position| map.is_blocked(position),
+    );
+}
+
+fn reset_flow_fields(mut commands: Commands) {
+    commands.insert_resource(FlowFields::default());
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<NavigatorMode>();
+    app.init_resource::<FlowFields>();
+
+    app.add_systems(
+        Update,
+        update_flow_fields
+            .run_if(in_state(Screen::Gameplay))
+            .run_if(resource_equals(NavigatorMode::FlowField)),
+    );
+    app.add_systems(OnExit(Screen::Gameplay), reset_flow_fields);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_flow_field() {
+        let blocked = [IVec2::new(0, -1), IVec2::new(0, 0), IVec2::new(0, 1)];
+        let field = FlowField::build(
+            IVec2::new(-2, -2),
+            IVec2::new(5, 5),
+            IVec2::new(2, 0),
+            |p| blocked.contains(&p),
+        );
+
+        assert_eq!(field.next(IVec2::new(2, 0)), Some(IVec2::new(2, 0)));
+        assert_eq!(field.next(IVec2::new(1, 0)), Some(IVec2::new(2, 0)));
+
+        //绕过墙前进，不穿过墙角
+        let mut position = IVec2::new(-2, 0);
+        for _ in 0..10 {
+            let next = field.next(position).unwrap();
+            assert!(!blocked.contains(&next));
+            if next == position {
+                break;
+            }
+            position = next;
+        }
+        assert_eq!(position, field.goal());
+
+        assert_eq!(field.next(IVec2::new(10, 10)), None);
+    }
+
+    #[test]
+    fn test_flow_field_repair() {
+        let (min, size) = (IVec2::new(-10, -10), IVec2::new(20, 20));
+        let goal = IVec2::new(5, 0);
+        let mut blocked = vec![];
+
+        let mut field = FlowField::build(min, size, goal, |p| blocked.contains(&p));
+        let original = field.clone();
+        let far = IVec2::new(-9, 9);
+
+        //单个格子变为障碍时只更新受影响的格子
+        blocked.push(IVec2::ZERO);
+        let updated = field.repair(&[IVec2::ZERO], |p| blocked.contains(&p));
+        let rebuilt = FlowField::build(min, size, goal, |p| blocked.contains(&p));
+
+        assert!(updated < field.next.len() / 4);
+        assert_eq!(field.distances, rebuilt.distances);
+        assert_eq!(field.next, rebuilt.next);
+        assert_eq!(field.next(far), original.next(far));
+
+        //移除障碍后恢复
+        blocked.clear();
+        field.repair(&[IVec2::ZERO], |p| blocked.contains(&p));
+        assert_eq!(field.distances, original.distances);
+        assert_eq!(field.next, original.next);
+
+        //一次变化多个格子，包括把一个区域围起来
+        let mut seed = 7u32;
+        for _ in 0..20 {
+            let mut changes = vec![];
+
+            for _ in 0..8 {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                let position =
+                    min + IVec2::new((seed >> 8) as i32 % size.x, (seed >> 20) as i32 % size.y);
+
+                if let Some(index) = blocked.iter().position(|p| *p == position) {
+                    blocked.remove(index);
+                } else {
+                    blocked.push(position);
+                }
+                changes.push(position);
+            }
+
+            field.repair(&changes, |p| blocked.contains(&p));
+            let rebuilt = FlowField::build(min, size, goal, |p| blocked.contains(&p));
+            assert_eq!(field.distances, rebuilt.distances);
+            assert_eq!(field.next, rebuilt.next);
+        }
+
+        let wall = [IVec2::new(-8, -9), IVec2::new(-9, -8), IVec2::new(-8, -8)];
+        blocked.extend(wall);
+        field.repair(&wall, |p| blocked.contains(&p));
+        let rebuilt = FlowField::build(min, size, goal, |p| blocked.contains(&p));
+        assert_eq!(field.distances, rebuilt.distances);
+        assert_eq!(field.next, rebuilt.next);
+    }
+
+    #[test]
+    fn test_flow_fields_update() {
+        let mut flow_fields = FlowFields::default();
+        let mut blocked = vec![];
+
+        let light_source = Entity::PLACEHOLDER;
+        let light_sources = [(light_source, IVec2::new(2, 0))];
+        let (min, size) = (IVec2::new(-2, -2), IVec2::new(5, 5));
+
+        let next = |flow_fields: &FlowFields| {
+            flow_fields
+                .get(light_source)
+                .and_then(|field| field.next(IVec2::ZERO))
+        };
+
+        let count = flow_fields.update(min, size, 0, &[], &light_sources, |p| blocked.contains(&p));
+        assert_eq!(count, 1);
+        assert_eq!(next(&flow_fields), Some(IVec2::new(1, 0)));
+
+        //障碍没有变化时不更新
+        let count = flow_fields.update(min, size, 0, &[], &light_sources, |p| blocked.contains(&p));
+        assert_eq!(count, 0);
+
+        //添加障碍后绕过障碍
+        blocked.push(IVec2::new(1, 0));
+        let count = flow_fields.update(min, size, 1, &blocked, &light_sources, |p| {
+            blocked.contains(&p)
+        });
+        assert_eq!(count, 1);
+        assert_eq!(flow_fields.obstacle_version(), 1);
+        assert_eq!(next(&flow_fields), Some(IVec2::new(0, -1)));
+
+        //移除障碍后恢复直线前进
+        let changes = std::mem::take(&mut blocked);
+        let count = flow_fields.update(min, size, 2, &changes, &light_sources, |p| {
+            blocked.contains(&p)
+        });
+        assert_eq!(count, 1);
+        assert_eq!(next(&flow_fields), Some(IVec2::new(1, 0)));
+
+        //移除光源后删除流场
+        flow_fields.update(min, size, 2, &[], &[], |p| blocked.contains(&p));
+        assert!(flow_fields.get(light_source).is_none());
+    }
+
+    #[test]
+    fn test_navigator_mode_for_map() {
+        assert_eq!(
+            NavigatorMode::FlowField.for_map(20, 20),
+            NavigatorMode::FlowField
+        );
+        assert_eq!(
+            NavigatorMode::FlowField.for_map(1000, 1000),
+            NavigatorMode::NavMesh
+        );
+        assert_eq!(
+            NavigatorMode::NavMesh.for_map(20, 20),
+            NavigatorMode::NavMesh
+        );
+    }
+}
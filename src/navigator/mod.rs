@@ -1,39 +1,225 @@
+mod flow_field;
+
+pub use flow_field::*;
+
 use avian2d::prelude::*;
-use bevy::prelude::*;
+use bevy::{platform::collections::HashMap, prelude::*};
 use vleue_navigator::{
     Triangulation, VleueNavigatorPlugin,
     prelude::{NavMeshSettings, NavMeshUpdateMode, NavmeshUpdaterPlugin},
 };
 
-use crate::consts::MAP_TIEM_SIZE;
+use crate::{consts::MAP_TIEM_SIZE, screens::Screen};
 
 #[derive(Component)]
 pub enum Obstacle {
     Wall,
+    //阻挡路径的单位
+    Unit,
 }
 
+///偏离路径超过该距离时重新寻路
+pub const NAVIGATOR_PATH_DRIFT_DISTANCE: f32 = MAP_TIEM_SIZE;
+
 #[derive(Component, Default)]
 pub struct NavigatorPath {
+    ///当前路段的起点
+    pub from: Vec3,
     pub current: Vec3,
     pub next: Vec<Vec3>,
 }
 
-pub fn spawn_nav_mesh(commands: &mut Commands) {
+impl NavigatorPath {
+    pub fn set_path(&mut self, start: Vec3, path: &[Vec3]) {
+        let Some((first, remaining)) = path.split_first() else {
+            return;
+        };
+
+        let mut remaining = remaining.to_vec();
+        remaining.reverse();
+
+        self.from = start;
+        self.current = *first;
+        self.next = remaining;
+    }
+
+    //前往下一个路径点
+    pub fn advance(&mut self) -> bool {
+        let Some(next) = self.next.pop() else {
+            return false;
+        };
+
+        self.from = self.current;
+        self.current = next;
+        true
+    }
+
+    //到当前路段的距离
+    pub fn distance_to_segment(&self, position: Vec3) -> f32 {
+        let start = self.from.truncate();
+        let end = self.current.truncate();
+        let position = position.truncate();
+
+        let segment = end - start;
+        let length_squared = segment.length_squared();
+        if length_squared <= f32::EPSILON {
+            return position.distance(end);
+        }
+
+        let t = ((position - start).dot(segment) / length_squared).clamp(0.0, 1.0);
+        position.distance(start + segment * t)
+    }
+
+    pub fn is_drifted(&self, position: Vec3) -> bool {
+        //还未计算过路径
+        if self.from == self.current {
+            return false;
+        }
+
+        self.distance_to_segment(position) > NAVIGATOR_PATH_DRIFT_DISTANCE
+    }
+}
+
+///需要重新计算路径的标记
+#[derive(Component, Default)]
+#[component(storage = "SparseSet")]
+pub struct NavigatorPathRequest;
+
+///每帧最多计算的路径数量，None 表示不限制
+#[derive(Resource)]
+pub struct NavigatorPathBudget {
+    pub max_per_frame: Option<usize>,
+}
+
+impl Default for NavigatorPathBudget {
+    fn default() -> Self {
+        NavigatorPathBudget {
+            max_per_frame: Some(32),
+        }
+    }
+}
+
+///跟随路径的参数
+#[derive(Resource)]
+pub struct NavigatorSteering {
+    ///到达路径点的半径
+    pub arrival_radius: f32,
+    ///转向的速度，越大转向越快
+    pub turn_rate: f32,
+    ///与其他敌人保持距离的半径
+    pub separation_radius: f32,
+    ///分离的权重
+    pub separation_weight: f32,
+}
+
+impl Default for NavigatorSteering {
+    fn default() -> Self {
+        NavigatorSteering {
+            arrival_radius: 50.0,
+            turn_rate: 8.0,
+            separation_radius: MAP_TIEM_SIZE / 2.0,
+            separation_weight: 1.5,
+        }
+    }
+}
+
+impl NavigatorSteering {
+    //远离附近的单位，越近排斥越强
+    pub fn separation(&self, position: Vec2, neighbors: impl Iterator<Item = Vec2>) -> Vec2 {
+        let mut force = Vec2::ZERO;
+
+        for neighbor in neighbors {
+            let offset = position - neighbor;
+            let distance = offset.length();
+            if distance <= f32::EPSILON || distance >= self.separation_radius {
+                continue;
+            }
+
+            force += offset / distance * (1.0 - distance / self.separation_radius);
+        }
+
+        force
+    }
+
+    //平滑地从当前速度转向目标速度
+    pub fn steer(&self, velocity: Vec2, target: Vec2, delta: f32) -> Vec2 {
+        let t = 1.0 - (-self.turn_rate * delta).exp();
+        velocity.lerp(target, t)
+    }
+}
+
+///按照分离半径划分的网格，计算分离时只查找周围格子中的单位
+#[derive(Debug)]
+pub struct SeparationGrid {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<Vec2>>,
+}
+
+impl SeparationGrid {
+    pub fn new(separation_radius: f32, positions: impl Iterator<Item = Vec2>) -> Self {
+        let mut grid = SeparationGrid {
+            cell_size: separation_radius.max(1.0),
+            cells: HashMap::default(),
+        };
+
+        for position in positions {
+            let cell = grid.get_cell(position);
+            grid.cells.entry(cell).or_default().push(position);
+        }
+
+        grid
+    }
+
+    fn get_cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+
+    //格子不小于分离半径，半径内的单位都在周围的9个格子中
+    pub fn neighbors(&self, position: Vec2) -> impl Iterator<Item = Vec2> + '_ {
+        let cell = self.get_cell(position);
+
+        (-1..=1)
+            .flat_map(move |x| (-1..=1).map(move |y| cell + IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+    }
+}
+
+///导航网格的边界，与地图大小一致
+#[derive(Component, Default)]
+pub struct NavMeshBounds {
+    pub size: Vec2,
+}
+
+impl NavMeshBounds {
+    pub fn get_outer_edges(&self) -> Triangulation {
+        let half_size = self.size / 2.0;
+
+        Triangulation::from_outer_edges(&[
+            vec2(-half_size.x, -half_size.y),
+            vec2(half_size.x, -half_size.y),
+            vec2(half_size.x, half_size.y),
+            vec2(-half_size.x, half_size.y),
+        ])
+    }
+}
+
+pub fn spawn_nav_mesh(commands: &mut Commands, size: Vec2) {
+    let bounds = NavMeshBounds { size };
+
     commands.spawn((
         NavMeshSettings {
             // Define the outer borders of the navmesh.
-            fixed: Triangulation::from_outer_edges(&[
-                vec2(-500.0, -500.0),
-                vec2(500.0, -500.0),
-                vec2(500.0, 500.0),
-                vec2(-500.0, 500.0),
-            ]),
+            fixed: bounds.get_outer_edges(),
             agent_radius: MAP_TIEM_SIZE / 2.0,
             simplify: 10.0,
             merge_steps: 1,
             ..default()
         },
+        bounds,
         NavMeshUpdateMode::Direct,
+        DespawnOnExit(Screen::Gameplay),
     ));
 }
 
@@ -42,4 +228,64 @@ pub(super) fn plugin(app: &mut App) {
         VleueNavigatorPlugin,
         NavmeshUpdaterPlugin::<Collider, Obstacle>::default(),
     ));
+
+    app.init_resource::<NavigatorPathBudget>();
+    app.init_resource::<NavigatorSteering>();
+
+    flow_field::plugin(app);
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::math::vec3;
+
+    use super::*;
+
+    #[test]
+    fn test_navigator_path() {
+        let mut path = NavigatorPath::default();
+        assert!(!path.is_drifted(vec3(1000.0, 0.0, 0.0)));
+
+        path.set_path(
+            Vec3::ZERO,
+            &[vec3(100.0, 0.0, 0.0), vec3(100.0, 100.0, 0.0)],
+        );
+        assert_eq!(path.current, vec3(100.0, 0.0, 0.0));
+        assert_eq!(path.distance_to_segment(vec3(50.0, 30.0, 0.0)), 30.0);
+        assert!(!path.is_drifted(vec3(50.0, 30.0, 0.0)));
+        assert!(path.is_drifted(vec3(50.0, 300.0, 0.0)));
+
+        assert!(path.advance());
+        assert_eq!(path.from, vec3(100.0, 0.0, 0.0));
+        assert_eq!(path.current, vec3(100.0, 100.0, 0.0));
+        assert!(!path.advance());
+    }
+
+    #[test]
+    fn test_navigator_steering() {
+        let steering = NavigatorSteering::default();
+
+        let force = steering.separation(
+            Vec2::ZERO,
+            [vec2(10.0, 0.0), vec2(1000.0, 0.0), Vec2::ZERO].into_iter(),
+        );
+        assert!(force.x < 0.0);
+        assert_eq!(force.y, 0.0);
+
+        let grid = SeparationGrid::new(
+            steering.separation_radius,
+            [vec2(10.0, 0.0), vec2(1000.0, 0.0), Vec2::ZERO].into_iter(),
+        );
+        assert_eq!(
+            steering.separation(Vec2::ZERO, grid.neighbors(Vec2::ZERO)),
+            force
+        );
+        assert_eq!(grid.neighbors(Vec2::ZERO).count(), 2);
+
+        let velocity = steering.steer(Vec2::X, Vec2::Y, 0.0);
+        assert_eq!(velocity, Vec2::X);
+
+        let velocity = steering.steer(Vec2::X, Vec2::Y, 1.0);
+        assert!(velocity.y > 0.99);
+    }
 }
@@ -19,6 +19,7 @@ pub(super) fn plugin(app: &mut App) {
             close_menu.run_if(
                 in_state(Screen::Gameplay)
                     .and(not(in_state(Menu::None)))
+                    .and(not(in_state(Menu::Results)))
                     .and(input_just_pressed(KeyCode::KeyP)),
             ),
         ),
@@ -9,10 +9,84 @@ pub struct SkillAttributeSet {
 }
 
 impl SkillAttributeSet {
+    ///根据属性名和基础值创建属性集
+    pub fn from_base_values(values: &[(&str, f32)]) -> Self {
+        let mut attribute_set = SkillAttributeSet::default();
+
+        for (name, value) in values.iter() {
+            let mut attribute = SkillAttribute {
+                name: name.to_string(),
+                ..Default::default()
+            };
+            attribute.update_base_value(*value);
+
+            attribute_set.add_skill_attribute(attribute);
+        }
+
+        attribute_set
+    }
+
     pub fn skill_attribute(&self, attribute_name: &str) -> Option<&SkillAttribute> {
         self.data.get(attribute_name)
     }
 
+    pub fn skill_attribute_mut(&mut self, attribute_name: &str) -> Option<&mut SkillAttribute> {
+        self.data.get_mut(attribute_name)
+    }
+
+    ///属性的实际值
+    pub fn get_current_value(&self, attribute_name: &str) -> Option<i32> {
+        self.data
+            .get(attribute_name)
+            .map(|attribute| attribute.get_current_value())
+    }
+
+    ///把属性的基础值设置为指定的数值
+    pub fn set_attribute_base_value(&mut self, attribute_name: &str, value: f32) {
+        let Some(attribute) = self.data.get(attribute_name) else {
+            tracing::error!("{} attribute_name not found.", attribute_name);
+            return;
+        };
+
+        let value = value - attribute.base_value;
+        self.update_attribute_base_value(attribute_name, value);
+    }
+
+    ///移除所有属性上指定来源的修改器
+    pub fn remove_modifier_with_source(&mut self, source: &str) {
+        let mut changes = vec![];
+
+        for attribute in self.data.values_mut() {
+            let old_value = attribute.get_current_value();
+            attribute.remove_modifier_with_source(source);
+            changes.push((
+                attribute.name.clone(),
+                old_value,
+                attribute.get_current_value(),
+            ));
+        }
+
+        for (attribute_name, old_value, new_value) in changes {
+            self.update_dependency_modifiers(&attribute_name, old_value, new_value);
+        }
+    }
+
+    ///给属性添加修改器，同一来源的修改器会被替换，并更新依赖该属性的其他属性
+    pub fn add_modifier(&mut self, attribute_name: &str, modifier: &SkillAttributeModifier) {
+        let Some(attribute) = self.data.get_mut(attribute_name) else {
+            tracing::error!("{} attribute_name not found.", attribute_name);
+            return;
+        };
+
+        let old_value = attribute.get_current_value();
+
+        attribute.remove_modifier_with_source(&modifier.source);
+        attribute.add_modifier(modifier);
+
+        let new_value = attribute.get_current_value();
+        self.update_dependency_modifiers(attribute_name, old_value, new_value);
+    }
+
     pub fn add_dependency_modifier(&mut self, dependency_modifier: AttributeDependencyModifier) {
         let source = dependency_modifier.get_source();
         self.dependency_modifiers
@@ -31,24 +105,35 @@ impl SkillAttributeSet {
             attribute.update_base_value(value);
 
             let new_value = attribute.get_current_value();
+            self.update_dependency_modifiers(attribute_name, old_value, new_value);
+        } else {
+            tracing::error!("{} attribute_name not found.", attribute_name);
+        }
+    }
 
-            if new_value != old_value {
-                let dependency_modifiers = self
-                    .dependency_modifiers
-                    .values()
-                    .filter(|dependency_modifier| dependency_modifier.source == attribute_name)
-                    .collect::<Vec<_>>();
+    //属性的实际值变化后更新依赖它的属性
+    fn update_dependency_modifiers(
+        &mut self,
+        attribute_name: &str,
+        old_value: i32,
+        new_value: i32,
+    ) {
+        if new_value == old_value {
+            return;
+        }
 
-                for dependency_modifier in dependency_modifiers.iter() {
-                    if let Some(target_attribute) = self.data.get_mut(&dependency_modifier.target) {
-                        dependency_modifier.update(target_attribute, new_value - old_value);
-                    } else {
-                        tracing::error!("{} attribute_name not found.", dependency_modifier.target);
-                    }
-                }
+        let dependency_modifiers = self
+            .dependency_modifiers
+            .values()
+            .filter(|dependency_modifier| dependency_modifier.source == attribute_name)
+            .collect::<Vec<_>>();
+
+        for dependency_modifier in dependency_modifiers.iter() {
+            if let Some(target_attribute) = self.data.get_mut(&dependency_modifier.target) {
+                dependency_modifier.update(target_attribute, new_value - old_value);
+            } else {
+                tracing::error!("{} attribute_name not found.", dependency_modifier.target);
             }
-        } else {
-            tracing::error!("{} attribute_name not found.", attribute_name);
         }
     }
 }
@@ -66,9 +151,23 @@ impl AttributeDependencyModifier {
     }
 
     pub fn update(&self, target: &mut SkillAttribute, value: i32) {
-        let mut modifier = SkillAttributeModifier::default();
-        modifier.source = self.get_source();
-        modifier.value = self.value * (value as f32);
+        let source = self.get_source();
+
+        //同一个依赖只保留一个修改器，数值累加
+        let current_value = target
+            .modifiers
+            .iter()
+            .filter(|modifier| modifier.source == source)
+            .map(|modifier| modifier.value)
+            .sum::<f32>();
+
+        target.remove_modifier_with_source(&source);
+
+        let modifier = SkillAttributeModifier {
+            value: current_value + self.value * (value as f32),
+            source,
+            ..Default::default()
+        };
 
         target.add_modifier(&modifier);
     }
@@ -266,6 +365,37 @@ mod test {
         assert_eq!(20, burden);
     }
 
+    #[test]
+    fn test_attribute_set_modifier() {
+        let mut attribute_set =
+            SkillAttributeSet::from_base_values(&[("power", 10.0), ("burden", 10.0)]);
+
+        attribute_set.add_dependency_modifier(AttributeDependencyModifier {
+            source: "power".to_string(),
+            target: "burden".to_string(),
+            value: 10.0,
+        });
+
+        let modifier = |value| SkillAttributeModifier {
+            value,
+            source: "skill".to_string(),
+            ..Default::default()
+        };
+
+        //同一来源的修改器被替换
+        attribute_set.add_modifier("power", &modifier(1.0));
+        attribute_set.add_modifier("power", &modifier(2.0));
+        assert_eq!(attribute_set.get_current_value("power"), Some(12));
+        assert_eq!(attribute_set.get_current_value("burden"), Some(30));
+
+        attribute_set.remove_modifier_with_source("skill");
+        assert_eq!(attribute_set.get_current_value("power"), Some(10));
+        assert_eq!(attribute_set.get_current_value("burden"), Some(10));
+
+        attribute_set.add_modifier("power", &modifier(2.0));
+        assert_eq!(attribute_set.get_current_value("burden"), Some(30));
+    }
+
     #[test]
     fn test_attribute() {
         let mut attribute = SkillAttribute::default();
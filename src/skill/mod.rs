@@ -1,14 +1,131 @@
 mod attribute;
+mod status;
 
 use std::{fmt::Debug, ops::Deref};
 
 pub use attribute::*;
-
-use bevy::{platform::collections::HashMap, prelude::*};
+pub use status::*;
+
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt, LoadContext, io::Reader},
+    ecs::system::SystemParam,
+    platform::collections::HashMap,
+    prelude::*,
+};
 use downcast_rs::{Downcast, impl_downcast};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::asset_tracking::LoadResource;
+
+#[derive(SystemParam)]
+pub struct SkillSystemParams<'w> {
+    skill_data_assets: Res<'w, SkillDataAssets>,
+    skill_data_set: Res<'w, Assets<SkillData>>,
+}
+
+impl SkillSystemParams<'_> {
+    pub fn get_skill_data(&self, item_name: &str) -> Option<SkillData> {
+        self.skill_data_assets
+            .assets
+            .get(item_name)
+            .and_then(|handle| self.skill_data_set.get(handle).cloned())
+    }
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct SkillDataAssets {
+    assets: HashMap<String, Handle<SkillData>>,
+}
+
+impl FromWorld for SkillDataAssets {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let mut assets = HashMap::new();
+
+        assets.insert(
+            "arrow".into(),
+            asset_server.load("skill/arrow.skill_data.yaml"),
+        );
+
+        assets.insert(
+            "lightning".into(),
+            asset_server.load("skill/lightning.skill_data.yaml"),
+        );
+
+        Self { assets }
+    }
+}
+
+#[derive(Default, TypePath)]
+pub struct SkillDataLoader;
+
+#[derive(Debug, Error)]
+pub enum SkillDataLoaderError {
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse Yaml: {0}")]
+    Yaml(#[from] yaml_serde::Error),
+}
+
+impl AssetLoader for SkillDataLoader {
+    type Asset = SkillData;
+
+    type Settings = ();
+
+    type Error = SkillDataLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut buffer = String::new();
+        reader.read_to_string(&mut buffer).await?;
+        let asset: SkillData = yaml_serde::from_str(&buffer)?;
+        Ok(asset)
+    }
+    fn extensions(&self) -> &[&str] {
+        &[".skill_data.yaml"]
+    }
+}
+
+///技能的定义
+#[derive(Debug, Clone, Asset, Reflect, Deserialize, Serialize)]
+pub struct SkillData {
+    pub item_name: String,
+    //技能的效果，按照顺序生效
+    pub effects: Vec<SkillEffectData>,
+}
+
+///技能效果的定义，name对应注册的技能效果
+#[derive(Debug, Clone, Reflect, Deserialize, Serialize)]
+pub struct SkillEffectData {
+    pub name: String,
+    //效果的参数
+    #[serde(default)]
+    pub values: Vec<SkillEffectValueData>,
+}
+
+impl SkillEffectData {
+    pub fn get_value(&self, name: &str) -> Option<f32> {
+        self.values
+            .iter()
+            .find(|value| value.name == name)
+            .map(|value| value.value)
+    }
+}
+
+#[derive(Debug, Clone, Reflect, Deserialize, Serialize)]
+pub struct SkillEffectValueData {
+    pub name: String,
+    pub value: f32,
+}
 
 ///技能运行过程数据
-#[derive(Debug, Component)]
+#[derive(Debug, Component, Clone)]
 pub struct SkillRunContext {
     pub source: Option<Entity>,
     pub caster: Entity,
@@ -65,17 +182,48 @@ impl<T: Clone + 'static + Sync + Send + Debug> SkillRunData for T {
     }
 }
 
-#[derive(Debug, Component)]
-pub struct Skill {}
+#[derive(Debug, Component, Clone, Default)]
+pub struct Skill {
+    item_name: String,
+    effects: Vec<SkillEffectData>,
+}
+
+impl Skill {
+    pub fn from_data(data: &SkillData) -> Self {
+        Skill {
+            item_name: data.item_name.clone(),
+            effects: data.effects.clone(),
+        }
+    }
+
+    pub fn item_name(&self) -> &str {
+        &self.item_name
+    }
+
+    pub fn effects(&self) -> &[SkillEffectData] {
+        &self.effects
+    }
+
+    ///只包含指定效果的技能，用于生成后续的技能效果
+    pub fn only_effect(&self, effect: &SkillEffectData) -> Skill {
+        Skill {
+            item_name: self.item_name.clone(),
+            effects: vec![effect.clone()],
+        }
+    }
+}
 
 pub trait FromSkill {
-    fn from_skill(skill: &Skill) -> Self;
+    fn from_skill(skill: &Skill, effect: &SkillEffectData) -> Self;
 }
 
-pub trait SkillCommand: 'static + Send + Sync + Debug {
-    fn execute(&self) {}
+///技能效果处理后需要执行的命令，在`SkillSystems::Execute`中执行
+pub trait SkillCommand: 'static + Send + Sync + Debug + Downcast {
+    fn execute(&self, world: &mut World);
 }
 
+impl_downcast!(SkillCommand);
+
 #[derive(Debug, Component)]
 pub struct SkillResponse {
     commands: Vec<Box<dyn SkillCommand>>,
@@ -88,13 +236,24 @@ impl SkillResponse {
         }
     }
 
-    pub fn execute(&self) {
-        for command in self.commands.iter() {
-            command.execute();
-        }
+    pub fn commands(&self) -> &[Box<dyn SkillCommand>] {
+        &self.commands
+    }
+
+    pub fn push<T: SkillCommand>(&mut self, command: T) {
+        self.commands.push(Box::new(command));
+    }
+
+    pub fn take_commands(&mut self) -> Vec<Box<dyn SkillCommand>> {
+        std::mem::take(&mut self.commands)
     }
 }
 
+///在执行阶段生成的技能效果，下一帧才会被处理
+#[derive(Debug, Component)]
+#[component(storage = "SparseSet")]
+pub struct SkillEffectPending;
+
 pub trait SkillRunContextDataBuilder: 'static + Sized {
     fn unique_name() -> &'static str;
 
@@ -119,9 +278,15 @@ impl SkillRunContextDataBuilder for () {
     fn update_skill_run_context_data(&self, _data: &mut SkillRunContextData) {}
 }
 
+///技能效果处理时使用的上下文，由效果自己从技能运行过程中构建
+pub trait SkillEffectContext: Sized {
+    ///entity为技能效果所在的实体
+    fn from_skill_run_context(entity: Entity, context: &SkillRunContext) -> Option<Self>;
+}
+
 pub trait SkillEffctProcessor {
     type Effect: FromSkill + Component;
-    type Context: SkillRunContextDataBuilder;
+    type Context: SkillEffectContext;
     type Response: SkillRunContextDataBuilder;
 
     fn process(
@@ -134,6 +299,7 @@ pub trait SkillEffctProcessor {
 
 #[derive(SystemSet, Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub enum SkillSystems {
+    Prepare,
     Update,
     Execute,
     Free,
@@ -141,11 +307,11 @@ pub enum SkillSystems {
 
 pub fn process_skill_effct<T: SkillEffctProcessor>(
     processor: &mut T,
-    mut skill_effct_q: Query<(&T::Effect, &mut SkillRunContext, &mut SkillResponse)>,
+    mut skill_effct_q: Query<(Entity, &T::Effect, &mut SkillRunContext, &mut SkillResponse)>,
 ) {
-    for (skill_effct, mut run_context, mut response) in skill_effct_q.iter_mut() {
+    for (entity, skill_effct, mut run_context, mut response) in skill_effct_q.iter_mut() {
         tracing::debug!("Skill effect process start.");
-        let Some(context) = T::Context::from_skill_run_context_data(&run_context.data) else {
+        let Some(context) = T::Context::from_skill_run_context(entity, &run_context) else {
             continue;
         };
 
@@ -155,7 +321,31 @@ pub fn process_skill_effct<T: SkillEffctProcessor>(
     }
 }
 
-fn free(mut commands: Commands, skill_effct_q: Query<Entity, With<SkillRunContext>>) {
+//开始处理上一帧生成的技能效果
+pub(crate) fn prepare(mut commands: Commands, pending_q: Query<Entity, With<SkillEffectPending>>) {
+    for entity in pending_q.iter() {
+        commands.entity(entity).remove::<SkillEffectPending>();
+    }
+}
+
+//按照技能效果的处理顺序执行命令
+pub(crate) fn execute(world: &mut World) {
+    let mut response_q = world.query::<&mut SkillResponse>();
+
+    let commands = response_q
+        .iter_mut(world)
+        .flat_map(|mut response| response.take_commands())
+        .collect::<Vec<_>>();
+
+    for command in commands.iter() {
+        command.execute(world);
+    }
+}
+
+pub(crate) fn free(
+    mut commands: Commands,
+    skill_effct_q: Query<Entity, (With<SkillRunContext>, Without<SkillEffectPending>)>,
+) {
     for entity in skill_effct_q.iter() {
         commands.entity(entity).despawn();
     }
@@ -165,6 +355,7 @@ pub(super) fn plugin(app: &mut App) {
     app.configure_sets(
         Last,
         (
+            SkillSystems::Prepare,
             SkillSystems::Update,
             SkillSystems::Execute,
             SkillSystems::Free,
@@ -172,5 +363,50 @@ pub(super) fn plugin(app: &mut App) {
             .chain(),
     );
 
+    app.add_systems(Last, prepare.in_set(SkillSystems::Prepare));
+    app.add_systems(Last, execute.in_set(SkillSystems::Execute));
     app.add_systems(Last, free.in_set(SkillSystems::Free));
+
+    app.init_asset_loader::<SkillDataLoader>();
+    app.init_asset::<SkillData>();
+    app.load_resource::<SkillDataAssets>();
+
+    status::plugin(app);
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[derive(Debug, Default, Resource)]
+    struct Counter(u32);
+
+    #[derive(Debug)]
+    struct CountSkillCommand;
+
+    impl SkillCommand for CountSkillCommand {
+        fn execute(&self, world: &mut World) {
+            world.resource_mut::<Counter>().0 += 1;
+        }
+    }
+
+    #[test]
+    fn test_execute_skill_response() {
+        let mut world = World::new();
+        world.init_resource::<Counter>();
+
+        let mut response = SkillResponse::empty();
+        response.push(CountSkillCommand);
+        response.push(CountSkillCommand);
+        world.spawn(response);
+
+        world.run_system_once(execute).unwrap();
+        assert_eq!(world.resource::<Counter>().0, 2);
+
+        //命令只执行一次
+        world.run_system_once(execute).unwrap();
+        assert_eq!(world.resource::<Counter>().0, 2);
+    }
 }
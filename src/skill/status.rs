@@ -0,0 +1,335 @@
+use std::time::Duration;
+
+use bevy::{platform::collections::HashSet, prelude::*};
+
+use super::{SkillAttributeModifier, SkillAttributeModifierOperation, SkillAttributeSet};
+
+///状态重复添加时的叠加方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatusStack {
+    ///替换为新的状态并重置持续时间
+    #[default]
+    Refresh,
+    ///增加层数并重置持续时间，层数不超过最大层数
+    Stack { max_stacks: u32 },
+}
+
+///状态周期触发时的数据
+#[derive(Debug, Clone)]
+pub struct StatusEffectTick {
+    pub owner: Entity,
+    pub caster: Option<Entity>,
+    pub value: f32,
+    pub stacks: u32,
+}
+
+///状态周期触发的回调，例如燃烧造成伤害
+pub type StatusEffectTickFn = fn(&StatusEffectTick, &mut Commands);
+
+///带有持续时间的状态，例如减速和燃烧
+#[derive(Debug, Clone)]
+pub struct StatusEffect {
+    pub name: String,
+    //施加状态的实体
+    pub caster: Option<Entity>,
+    pub stack: StatusStack,
+    stacks: u32,
+    timer: Timer,
+    //属性名和修改器，数值按照层数叠加
+    modifiers: Vec<(String, SkillAttributeModifier)>,
+    //周期触发的计时器和回调
+    tick: Option<(Timer, StatusEffectTickFn)>,
+    //回调使用的数值
+    pub value: f32,
+}
+
+impl StatusEffect {
+    pub fn new(name: &str, duration: f32) -> Self {
+        StatusEffect {
+            name: name.to_string(),
+            caster: None,
+            stack: StatusStack::default(),
+            stacks: 1,
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+            modifiers: vec![],
+            tick: None,
+            value: 0.0,
+        }
+    }
+
+    pub fn with_caster(mut self, caster: Entity) -> Self {
+        self.caster = Some(caster);
+        self
+    }
+
+    pub fn with_stack(mut self, stack: StatusStack) -> Self {
+        self.stack = stack;
+        self
+    }
+
+    pub fn with_modifier(
+        mut self,
+        attribute_name: &str,
+        operation: SkillAttributeModifierOperation,
+        value: f32,
+    ) -> Self {
+        let modifier = SkillAttributeModifier {
+            operation,
+            value,
+            source: StatusEffect::get_source(&self.name),
+            ..Default::default()
+        };
+
+        self.modifiers.push((attribute_name.to_string(), modifier));
+        self
+    }
+
+    pub fn with_tick(mut self, interval: f32, value: f32, callback: StatusEffectTickFn) -> Self {
+        //间隔为0时计时器每一帧触发无数次
+        self.tick = Some((
+            Timer::from_seconds(interval.max(0.01), TimerMode::Repeating),
+            callback,
+        ));
+        self.value = value;
+        self
+    }
+
+    pub fn get_source(name: &str) -> String {
+        format!("__status_effect_{}__", name)
+    }
+
+    pub fn stacks(&self) -> u32 {
+        self.stacks
+    }
+
+    //层数叠加后的修改器，覆盖不叠加
+    fn stacked_modifiers(&self) -> impl Iterator<Item = (&String, SkillAttributeModifier)> {
+        self.modifiers.iter().map(|(attribute_name, modifier)| {
+            let mut modifier = modifier.clone();
+
+            if modifier.operation != SkillAttributeModifierOperation::Overload {
+                modifier.value *= self.stacks as f32;
+            }
+
+            (attribute_name, modifier)
+        })
+    }
+}
+
+///实体身上的所有状态
+#[derive(Debug, Default, Component)]
+pub struct StatusEffects {
+    effects: Vec<StatusEffect>,
+    //修改器需要更新的状态
+    dirty: HashSet<String>,
+}
+
+impl StatusEffects {
+    pub fn get(&self, name: &str) -> Option<&StatusEffect> {
+        self.effects.iter().find(|effect| effect.name == name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    ///添加状态，已有同名的状态时按照叠加方式处理
+    pub fn apply(&mut self, effect: StatusEffect) {
+        self.dirty.insert(effect.name.clone());
+
+        let Some(existing) = self
+            .effects
+            .iter_mut()
+            .find(|item| item.name == effect.name)
+        else {
+            self.effects.push(effect);
+            return;
+        };
+
+        match existing.stack {
+            StatusStack::Refresh => {
+                *existing = effect;
+            }
+            StatusStack::Stack { max_stacks } => {
+                let stacks = (existing.stacks + 1).min(max_stacks.max(1));
+                let tick = existing.tick.take();
+
+                *existing = effect;
+                existing.stacks = stacks;
+
+                //保持原有的触发节奏
+                if let (Some((timer, _)), Some((old_timer, _))) = (existing.tick.as_mut(), tick) {
+                    *timer = old_timer;
+                }
+            }
+        }
+    }
+
+    ///移除状态
+    pub fn remove(&mut self, name: &str) {
+        self.effects.retain(|effect| effect.name != name);
+        self.dirty.insert(name.to_string());
+    }
+
+    ///推进所有状态的时间，移除结束的状态并返回需要触发的回调
+    pub fn tick(
+        &mut self,
+        owner: Entity,
+        delta: Duration,
+    ) -> Vec<(StatusEffectTickFn, StatusEffectTick)> {
+        let mut ticks = vec![];
+
+        for effect in self.effects.iter_mut() {
+            if let Some((timer, callback)) = effect.tick.as_mut() {
+                timer.tick(delta);
+
+                for _ in 0..timer.times_finished_this_tick() {
+                    ticks.push((
+                        *callback,
+                        StatusEffectTick {
+                            owner,
+                            caster: effect.caster,
+                            value: effect.value,
+                            stacks: effect.stacks,
+                        },
+                    ));
+                }
+            }
+
+            if effect.timer.tick(delta).is_finished() {
+                self.dirty.insert(effect.name.clone());
+            }
+        }
+
+        self.effects.retain(|effect| !effect.timer.is_finished());
+
+        ticks
+    }
+
+    ///把状态的修改器同步到属性上
+    pub fn update_attribute_set(&mut self, attribute_set: &mut SkillAttributeSet) {
+        for name in self.dirty.drain() {
+            attribute_set.remove_modifier_with_source(&StatusEffect::get_source(&name));
+
+            let Some(effect) = self.effects.iter().find(|effect| effect.name == name) else {
+                continue;
+            };
+
+            for (attribute_name, modifier) in effect.stacked_modifiers() {
+                attribute_set.add_modifier(attribute_name, &modifier);
+            }
+        }
+    }
+}
+
+//推进状态的时间，触发回调并更新属性
+fn update_status_effects(
+    mut commands: Commands,
+    mut status_effects_q: Query<(Entity, &mut StatusEffects, Option<&mut SkillAttributeSet>)>,
+    time: Res<Time>,
+) {
+    for (entity, mut status_effects, attribute_set) in status_effects_q.iter_mut() {
+        for (callback, tick) in status_effects.tick(entity, time.delta()) {
+            callback(&tick, &mut commands);
+        }
+
+        if let Some(mut attribute_set) = attribute_set {
+            status_effects.update_attribute_set(&mut attribute_set);
+        }
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Update, update_status_effects);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn attribute_set() -> SkillAttributeSet {
+        SkillAttributeSet::from_base_values(&[("speed", 100.0)])
+    }
+
+    fn speed(attribute_set: &SkillAttributeSet) -> i32 {
+        attribute_set
+            .skill_attribute("speed")
+            .unwrap()
+            .get_current_value()
+    }
+
+    fn tick_count(_tick: &StatusEffectTick, _commands: &mut Commands) {}
+
+    #[test]
+    fn test_status_effect_refresh() {
+        let mut attribute_set = attribute_set();
+        let mut status_effects = StatusEffects::default();
+        let owner = Entity::PLACEHOLDER;
+
+        let slow = |value| {
+            StatusEffect::new("slow", 1.0).with_modifier(
+                "speed",
+                SkillAttributeModifierOperation::Percentage,
+                value,
+            )
+        };
+
+        status_effects.apply(slow(-0.5));
+        status_effects.update_attribute_set(&mut attribute_set);
+        assert_eq!(speed(&attribute_set), 50);
+
+        status_effects.tick(owner, Duration::from_secs_f32(0.6));
+        status_effects.apply(slow(-0.3));
+        status_effects.update_attribute_set(&mut attribute_set);
+        assert_eq!(speed(&attribute_set), 70);
+        assert_eq!(status_effects.get("slow").unwrap().stacks(), 1);
+
+        //刷新后重新计时
+        status_effects.tick(owner, Duration::from_secs_f32(0.6));
+        assert!(status_effects.contains("slow"));
+
+        status_effects.tick(owner, Duration::from_secs_f32(0.6));
+        status_effects.update_attribute_set(&mut attribute_set);
+        assert!(!status_effects.contains("slow"));
+        assert_eq!(speed(&attribute_set), 100);
+    }
+
+    #[test]
+    fn test_status_effect_stack() {
+        let mut attribute_set = attribute_set();
+        let mut status_effects = StatusEffects::default();
+        let owner = Entity::PLACEHOLDER;
+
+        let poison = || {
+            StatusEffect::new("poison", 3.0)
+                .with_stack(StatusStack::Stack { max_stacks: 2 })
+                .with_modifier("speed", SkillAttributeModifierOperation::Absolute, -10.0)
+                .with_tick(1.0, 2.0, tick_count)
+        };
+
+        for _ in 0..3 {
+            status_effects.apply(poison());
+        }
+        status_effects.update_attribute_set(&mut attribute_set);
+        assert_eq!(status_effects.get("poison").unwrap().stacks(), 2);
+        assert_eq!(speed(&attribute_set), 80);
+
+        let ticks = status_effects.tick(owner, Duration::from_secs_f32(2.5));
+        assert_eq!(ticks.len(), 2);
+        assert_eq!(ticks[0].1.stacks, 2);
+        assert_eq!(ticks[0].1.value, 2.0);
+
+        status_effects.remove("poison");
+        status_effects.update_attribute_set(&mut attribute_set);
+        assert_eq!(speed(&attribute_set), 100);
+    }
+
+    #[test]
+    fn test_status_effect_zero_interval() {
+        let mut status_effects = StatusEffects::default();
+        status_effects.apply(StatusEffect::new("burn", 1.0).with_tick(0.0, 1.0, tick_count));
+
+        let ticks = status_effects.tick(Entity::PLACEHOLDER, Duration::from_secs_f32(0.1));
+        assert_eq!(ticks.len(), 10);
+    }
+}
@@ -1,13 +1,13 @@
 use bevy::prelude::*;
 
 use crate::{
-    battle::{self, BulletContext},
+    battle::{self, BulletContext, SkillEffectContainer},
     common::{
-        EnemyTargets, GameLayer, StateChart, StateChartPlugin, StateChartSets,
-        spawn_attack_distance,
+        ATTACK_DAMAGE, EnemyTargets, GameLayer, StateChart, StateChartPlugin, StateChartSets,
+        TargetSelector, TargetingStrategy, spawn_attack_distance,
     },
     enemy::Enemy,
-    skill::{Skill, SkillRunContextData, SkillRunContextDataBuilder},
+    skill::{Skill, SkillAttributeSet, SkillRunContextData, SkillRunContextDataBuilder},
     unit::{CooldownTimer, EnableState, FirstCreate, IdleState, Unit, UnitData, UnitFactory},
 };
 
@@ -73,18 +73,24 @@ fn active_2_enable_on_enable_event(
 pub struct ArrowTowerFactory;
 
 impl UnitFactory for ArrowTowerFactory {
-    fn spawn(&self, _data: &UnitData, entity_commands: &mut EntityCommands) {
+    fn spawn(&self, data: &UnitData, entity_commands: &mut EntityCommands) {
         let mut state_chart = StateChart::<ArrowTowerStateEvent>::default();
         state_chart.send_event(ArrowTowerStateEvent::Enable);
 
-        entity_commands.insert((state_chart, IdleState, ArrowTower, Name::new("ArrowTower")));
+        entity_commands.insert((
+            state_chart,
+            IdleState,
+            ArrowTower,
+            data.targeting,
+            Name::new("ArrowTower"),
+        ));
 
         let mut command = entity_commands.commands();
 
         let unit_attack_distance_layers = GameLayer::unit_attack_distance_layers();
 
         let unit_attack_distance =
-            spawn_attack_distance(&mut command, 500.0, unit_attack_distance_layers);
+            spawn_attack_distance(&mut command, data.range, unit_attack_distance_layers);
 
         entity_commands.add_child(unit_attack_distance);
     }
@@ -109,18 +115,21 @@ fn on_actvie_update(
     mut commands: Commands,
     mut arrow_tower_q: Query<
         (
-            &mut Unit,
+            &SkillAttributeSet,
             &Skill,
             Entity,
             &EnemyTargets,
             &GlobalTransform,
             &mut StateChart<ArrowTowerStateEvent>,
+            Option<&TargetingStrategy>,
         ),
         (With<ActiveState>, With<ArrowTower>),
     >,
     enemy_q: Query<&GlobalTransform, With<Enemy>>,
+    target_selector: TargetSelector,
+    skill_effect_container: Res<SkillEffectContainer>,
 ) {
-    for (mut _unit, skill, entity, enemy_targets, unit_position, mut start_chart) in
+    for (attribute_set, skill, entity, enemy_targets, unit_position, mut start_chart, strategy) in
         arrow_tower_q.iter_mut()
     {
         if enemy_targets.0.is_empty() {
@@ -130,18 +139,32 @@ fn on_actvie_update(
         start_chart.send_event(ArrowTowerStateEvent::Enable);
         commands.entity(entity).remove::<CooldownTimer>();
 
-        let target = *enemy_targets.0.first().unwrap();
+        let strategy = strategy.copied().unwrap_or_default();
+
+        let Some(target) = target_selector.select(
+            strategy,
+            unit_position.translation().truncate(),
+            enemy_targets,
+        ) else {
+            continue;
+        };
+
         let Ok(target_position) = enemy_q.get(target) else {
             return;
         };
 
         let direction =
             target_position.translation().truncate() - unit_position.translation().truncate();
-        let direction = direction.normalize();
+        let direction = direction.normalize_or_zero();
 
+        //其他技能效果从技能运行过程中读取施法者和目标
         let context = BulletContext {
+            caster: entity,
+            damage: attribute_set
+                .get_current_value(ATTACK_DAMAGE)
+                .unwrap_or_default(),
             layers: GameLayer::unit_hitbox_layers(),
-            direction: direction,
+            direction,
             bullet_position: unit_position.translation().truncate(),
         };
 
@@ -150,7 +173,15 @@ fn on_actvie_update(
         context.update_skill_run_context_data(&mut data);
 
         tracing::debug!("Skill start.");
-        battle::execute_skill(&mut commands, skill, entity, vec![target], None, data);
+        battle::execute_skill(
+            &mut commands,
+            &skill_effect_container,
+            skill,
+            entity,
+            vec![target],
+            None,
+            data,
+        );
     }
 }
 
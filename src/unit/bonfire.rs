@@ -3,7 +3,7 @@ use bevy::prelude::*;
 use crate::{
     common::{LightSource, StateChart, StateChartPlugin, StateChartSets},
     product::ProductMeta,
-    unit::{CooldownTimer, EnableState, IdleState, Unit, UnitFactory},
+    unit::{CooldownTimer, EnableState, IdleState, Unit, UnitData, UnitFactory},
 };
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -55,16 +55,17 @@ fn on_cooldown_timer_finished(
 
 #[derive(Debug, Component)]
 pub struct Bonfire {
-    products: Vec<ProductMeta>,
+    pub(super) products: Vec<ProductMeta>,
 }
 
-impl Default for Bonfire {
-    fn default() -> Self {
+impl Bonfire {
+    pub fn from_data(data: &UnitData) -> Self {
         Bonfire {
-            products: vec![ProductMeta {
-                name: "sunlight".to_string(),
-                value: 10.0,
-            }],
+            products: data
+                .products
+                .iter()
+                .map(|product| product.to_product_meta())
+                .collect(),
         }
     }
 }
@@ -73,13 +74,13 @@ impl Default for Bonfire {
 pub struct BonfireFactory;
 
 impl UnitFactory for BonfireFactory {
-    fn spawn(&self, _data: &super::UnitData, commands: &mut EntityCommands) {
+    fn spawn(&self, data: &UnitData, commands: &mut EntityCommands) {
         let mut state_chart = StateChart::<BonfireStateEvent>::default();
         state_chart.send_event(BonfireStateEvent::Enable);
 
         commands.insert((
-            Bonfire::default(),
-            LightSource,
+            Bonfire::from_data(data),
+            LightSource::default(),
             Name::new("Bonfire"),
             IdleState,
             state_chart,
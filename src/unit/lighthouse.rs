@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+
+use crate::{
+    common::LightSource,
+    unit::{UnitData, UnitFactory},
+};
+
+///灯塔，驱散黑暗，削弱巢穴
+#[derive(Debug, Component)]
+pub struct Lighthouse;
+
+#[derive(Debug)]
+pub struct LighthouseFactory;
+
+impl UnitFactory for LighthouseFactory {
+    fn spawn(&self, _data: &UnitData, commands: &mut EntityCommands) {
+        commands.insert((
+            Lighthouse,
+            LightSource { brightness: 2.0 },
+            Name::new("Lighthouse"),
+        ));
+    }
+}
@@ -1,15 +1,25 @@
 mod arrow_tower;
 mod bonfire;
+mod lighthouse;
+mod upgrade;
 
 pub use arrow_tower::*;
 pub use bonfire::*;
+pub use lighthouse::*;
+pub use upgrade::*;
 
 use std::{fmt::Debug, time::Duration};
 
 use crate::{
     asset_tracking::LoadResource,
-    common::{EnemyTargets, GameLayer, Stas, spawn_hurt},
-    skill::Skill,
+    common::{
+        ATTACK_SPEED, CombatStats, EnemyTargets, GameLayer, Health, TargetingStrategy,
+        get_max_health, scale_by_attack_speed, spawn_hurt,
+    },
+    map::{EnvironmentModifiers, MapEnvironmentData},
+    navigator::Obstacle,
+    product::ProductMeta,
+    skill::{Skill, SkillAttributeSet, SkillSystemParams},
 };
 use avian2d::prelude::*;
 use bevy::{
@@ -21,11 +31,15 @@ use bevy::{
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+///出售单位时默认返还的阳光比例
+pub const DEFAULT_REFUND_RATE: f32 = 0.5;
+
 #[derive(SystemParam)]
 pub struct UnitSystemParams<'w> {
     pub unit_factory_container: Res<'w, UnitFactoryContainer>,
     unit_data_assets: Res<'w, UnitDataAssets>,
     unit_data_set: Res<'w, Assets<UnitData>>,
+    skill_system_params: SkillSystemParams<'w>,
 }
 
 impl UnitSystemParams<'_> {
@@ -35,6 +49,18 @@ impl UnitSystemParams<'_> {
             .get(item_name)
             .and_then(|handle| self.unit_data_set.get(handle).cloned())
     }
+
+    ///单位使用的技能，没有配置技能时返回None
+    pub fn get_unit_skill(&self, data: &UnitData) -> Option<Skill> {
+        let skill_name = data.skill.as_ref()?;
+
+        let Some(skill_data) = self.skill_system_params.get_skill_data(skill_name) else {
+            tracing::error!("{} skill data not found.", skill_name);
+            return None;
+        };
+
+        Some(Skill::from_data(&skill_data))
+    }
 }
 
 #[derive(Resource, Asset, Clone, Reflect)]
@@ -58,6 +84,16 @@ impl FromWorld for UnitDataAssets {
             asset_server.load("unit/arrow_tower.unit_data.yaml"),
         );
 
+        assets.insert(
+            "lighthouse".into(),
+            asset_server.load("unit/lighthouse.unit_data.yaml"),
+        );
+
+        assets.insert(
+            "tesla_tower".into(),
+            asset_server.load("unit/tesla_tower.unit_data.yaml"),
+        );
+
         Self { assets }
     }
 }
@@ -70,10 +106,20 @@ pub struct IdleState;
 #[component(storage = "SparseSet")]
 pub struct EnableState;
 
-//更新技能冷却
-fn on_cooldown_timer_update(mut cooldown_timer_q: Query<(&mut CooldownTimer,)>, time: Res<Time>) {
-    for (mut cooldown_timer,) in cooldown_timer_q.iter_mut() {
-        cooldown_timer.timer.tick(time.delta());
+//更新技能冷却，冷却受攻击速度影响
+fn on_cooldown_timer_update(
+    mut cooldown_timer_q: Query<(&mut CooldownTimer, Option<&SkillAttributeSet>)>,
+    time: Res<Time>,
+) {
+    for (mut cooldown_timer, attribute_set) in cooldown_timer_q.iter_mut() {
+        let delta = match attribute_set.and_then(|set| set.get_current_value(ATTACK_SPEED)) {
+            Some(attack_speed) => {
+                Duration::from_secs_f32(scale_by_attack_speed(time.delta_secs(), attack_speed))
+            }
+            None => time.delta(),
+        };
+
+        cooldown_timer.timer.tick(delta);
     }
 }
 
@@ -82,13 +128,22 @@ pub fn spawn_unit(
     asset_server: &AssetServer,
     position: Vec3,
     data: &UnitData,
+    skill: Option<Skill>,
     container: &UnitFactoryContainer,
-) {
+) -> Option<Entity> {
     if let Some(factory) = container.0.get(&data.item_name) {
         let unit = Unit::from_data(data);
-        unit.spawn_unit(commands, asset_server, position, data, factory.as_ref());
+        Some(unit.spawn_unit(
+            commands,
+            asset_server,
+            position,
+            data,
+            skill,
+            factory.as_ref(),
+        ))
     } else {
         tracing::error!("{} factory not match.", data.item_name);
+        None
     }
 }
 
@@ -100,6 +155,9 @@ impl Default for UnitFactoryContainer {
         let mut container = UnitFactoryContainer::empty();
         container.register("arrow_tower", ArrowTowerFactory);
         container.register("bonfire", BonfireFactory);
+        container.register("lighthouse", LighthouseFactory);
+        //电塔和箭塔的行为相同，只是技能不同
+        container.register("tesla_tower", ArrowTowerFactory);
 
         container
     }
@@ -155,9 +213,77 @@ pub struct UnitData {
     pub image: String,
     //冷却倒计时，单位为秒
     pub cooldown_timer: u64,
+    //放置所需的阳光
+    pub cost: u32,
+    //单位存在期间对环境属性的修改
+    #[serde(default)]
+    pub environment_modifiers: Vec<MapEnvironmentData>,
+    //攻击的伤害
+    #[serde(default)]
+    pub damage: i32,
+    //攻击范围
+    #[serde(default)]
+    pub range: f32,
+    //选择攻击目标的策略
+    #[serde(default)]
+    pub targeting: TargetingStrategy,
+    //生产的产品
+    #[serde(default)]
+    pub products: Vec<UnitProductData>,
+    //升级的等级，按照顺序升级
+    #[serde(default)]
+    pub upgrades: Vec<UnitUpgradeData>,
+    //出售时返还的阳光比例，默认为`DEFAULT_REFUND_RATE`
+    #[serde(default)]
+    pub refund_rate: Option<f32>,
+    //是否阻挡敌人的路径
+    #[serde(default)]
+    pub blocks_path: bool,
+    //使用的技能，对应`assets/skill`目录下的技能文件
+    #[serde(default)]
+    pub skill: Option<String>,
+}
+
+///单位生产的产品
+#[derive(Debug, Clone, Reflect, Deserialize, Serialize)]
+pub struct UnitProductData {
+    pub name: String,
+    pub value: f32,
+}
+
+impl UnitProductData {
+    pub fn to_product_meta(&self) -> ProductMeta {
+        ProductMeta {
+            name: self.name.clone(),
+            value: self.value,
+        }
+    }
+}
+
+///单位的一个升级等级，没有配置的属性保持不变
+#[derive(Debug, Clone, Default, Reflect, Deserialize, Serialize)]
+pub struct UnitUpgradeData {
+    //升级所需的阳光
+    pub cost: u32,
+    #[serde(default)]
+    pub cooldown_timer: Option<u64>,
+    #[serde(default)]
+    pub range: Option<f32>,
+    #[serde(default)]
+    pub damage: Option<i32>,
+    #[serde(default)]
+    pub products: Option<Vec<UnitProductData>>,
 }
 
 impl UnitData {
+    pub fn get_combat_stats(&self) -> CombatStats {
+        CombatStats {
+            attack_damage: self.damage as f32,
+            range: self.range,
+            ..default()
+        }
+    }
+
     pub fn get_unit_image(&self, asset_server: &AssetServer) -> Handle<Image> {
         asset_server.load(&format!("images/unit/{}.png", self.image))
     }
@@ -185,30 +311,60 @@ impl CooldownTimer {
 
 #[derive(Debug, Component, Clone, Default)]
 pub struct Unit {
+    item_name: String,
+    //当前的升级等级，从0开始
+    level: usize,
     cooldown_timer: u64,
+    //放置和升级花费的阳光
+    invested: u32,
 }
 
 impl Unit {
     pub fn from_data(data: &UnitData) -> Self {
         Unit {
+            item_name: data.item_name.clone(),
+            level: 0,
             cooldown_timer: data.cooldown_timer,
+            invested: data.cost,
         }
     }
 
+    pub fn item_name(&self) -> &str {
+        &self.item_name
+    }
+
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    ///出售时返还的阳光
+    pub fn refund(&self, data: &UnitData) -> u32 {
+        let rate = data
+            .refund_rate
+            .unwrap_or(DEFAULT_REFUND_RATE)
+            .clamp(0.0, 1.0);
+
+        (self.invested as f32 * rate).floor() as u32
+    }
+
     pub fn spawn_unit(
         &self,
         commands: &mut EntityCommands,
         asset_server: &AssetServer,
         position: Vec3,
         data: &UnitData,
+        skill: Option<Skill>,
         factory: &dyn UnitFactory,
-    ) {
+    ) -> Entity {
         let image = data.get_unit_image(asset_server);
 
         let unit_layers = GameLayer::unit_layers();
 
+        let parent = commands.id();
+
         let mut commands = commands.commands();
         let collider = Collider::rectangle(100.0, 100.0);
+        let attribute_set = data.get_combat_stats().to_attribute_set();
 
         let mut entity_commands = commands.spawn((
             self.clone(),
@@ -226,8 +382,8 @@ impl Unit {
             unit_layers,
             EnemyTargets::default(),
             CooldownTimer::new(self.cooldown_timer),
-            Skill {},
-            Stas::default(),
+            Health::new(get_max_health(&attribute_set)),
+            attribute_set,
             FirstCreate,
         ));
 
@@ -237,7 +393,25 @@ impl Unit {
             GameLayer::unit_hurtbox_layers(),
         );
 
+        if !data.environment_modifiers.is_empty() {
+            entity_commands.insert(EnvironmentModifiers(data.environment_modifiers.clone()));
+        }
+
+        if let Some(skill) = skill {
+            entity_commands.insert(skill);
+        }
+
+        if data.blocks_path {
+            entity_commands.insert(Obstacle::Unit);
+        }
+
         factory.spawn(data, &mut entity_commands);
+
+        let unit = entity_commands.id();
+
+        commands.entity(parent).add_child(unit);
+
+        unit
     }
 }
 
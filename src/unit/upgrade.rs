@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    common::{ATTACK_DAMAGE, RANGE},
+    skill::SkillAttributeSet,
+    unit::{Bonfire, CooldownTimer, Unit, UnitSystemParams, UnitUpgradeData},
+};
+
+#[derive(SystemParam)]
+pub struct UnitUpgradeSystemParams<'w, 's> {
+    unit_system_params: UnitSystemParams<'w>,
+    unit_q: Query<
+        'w,
+        's,
+        (
+            &'static mut Unit,
+            Option<&'static mut CooldownTimer>,
+            Option<&'static mut Bonfire>,
+            Option<&'static mut SkillAttributeSet>,
+        ),
+    >,
+}
+
+impl UnitUpgradeSystemParams<'_, '_> {
+    pub fn get_unit(&self, entity: Entity) -> Option<&Unit> {
+        self.unit_q.get(entity).ok().map(|(unit, _, _, _)| unit)
+    }
+
+    ///单位的下一个升级等级，已经满级时为None
+    pub fn next_upgrade(&self, entity: Entity) -> Option<UnitUpgradeData> {
+        let (unit, _, _, _) = self.unit_q.get(entity).ok()?;
+        let data = self.unit_system_params.get_unit_data(unit.item_name())?;
+
+        data.upgrades.get(unit.level()).cloned()
+    }
+
+    ///出售单位时返还的阳光
+    pub fn get_refund(&self, entity: Entity) -> Option<u32> {
+        let unit = self.get_unit(entity)?;
+        let data = self.unit_system_params.get_unit_data(unit.item_name())?;
+
+        Some(unit.refund(&data))
+    }
+
+    ///将升级应用到单位上
+    pub fn apply_upgrade(&mut self, entity: Entity, upgrade: &UnitUpgradeData) {
+        let Ok((mut unit, cooldown_timer, bonfire, attribute_set)) = self.unit_q.get_mut(entity)
+        else {
+            return;
+        };
+
+        unit.level += 1;
+        unit.invested += upgrade.cost;
+
+        if let Some(secs) = upgrade.cooldown_timer {
+            unit.cooldown_timer = secs;
+
+            if let Some(mut cooldown_timer) = cooldown_timer {
+                cooldown_timer.timer.set_duration(Duration::from_secs(secs));
+            }
+        }
+
+        if let Some(products) = upgrade.products.as_ref() {
+            if let Some(mut bonfire) = bonfire {
+                bonfire.products = products
+                    .iter()
+                    .map(|product| product.to_product_meta())
+                    .collect();
+            }
+        }
+
+        //修改属性的基础值，攻击范围会随着属性更新
+        if let Some(mut attribute_set) = attribute_set {
+            if let Some(damage) = upgrade.damage {
+                attribute_set.set_attribute_base_value(ATTACK_DAMAGE, damage as f32);
+            }
+
+            if let Some(range) = upgrade.range {
+                attribute_set.set_attribute_base_value(RANGE, range);
+            }
+        }
+
+        tracing::info!("{} upgrade to level {}.", unit.item_name(), unit.level);
+    }
+}